@@ -15,13 +15,14 @@ use casper_contract::{contract_api::runtime, unwrap_or_revert::UnwrapOrRevert};
 
 use casper_erc20::{
     constants::{
-        ADDRESS_RUNTIME_ARG_NAME, AMOUNT_RUNTIME_ARG_NAME, DECIMALS_RUNTIME_ARG_NAME,
-        NAME_RUNTIME_ARG_NAME, OWNER_RUNTIME_ARG_NAME, RECIPIENT_RUNTIME_ARG_NAME,
+        ADDRESS_RUNTIME_ARG_NAME, AMOUNT_RUNTIME_ARG_NAME, DEADLINE_RUNTIME_ARG_NAME,
+        DECIMALS_RUNTIME_ARG_NAME, NAME_RUNTIME_ARG_NAME, OWNER_RUNTIME_ARG_NAME,
+        PUBLIC_KEY_RUNTIME_ARG_NAME, RECIPIENT_RUNTIME_ARG_NAME, SIGNATURE_RUNTIME_ARG_NAME,
         SPENDER_RUNTIME_ARG_NAME, SYMBOL_RUNTIME_ARG_NAME, TOTAL_SUPPLY_RUNTIME_ARG_NAME,
     },
     Address, ERC20, Error,
 };
-use casper_types::{U256, system::CallStackElement};
+use casper_types::{PublicKey, Signature, U256, system::CallStackElement};
 // use casper_types::u8;
 use casper_types::CLValue;
 
@@ -106,6 +107,24 @@ pub extern "C" fn allowance(){
     runtime::ret(CLValue::from_t(amount).unwrap_or_revert());
 }
 
+#[no_mangle]
+pub extern "C" fn permit(){
+    let public_key: PublicKey = runtime::get_named_arg(PUBLIC_KEY_RUNTIME_ARG_NAME);
+    let spender: Address = runtime::get_named_arg(SPENDER_RUNTIME_ARG_NAME);
+    let amount: U256 = runtime::get_named_arg(AMOUNT_RUNTIME_ARG_NAME);
+    let deadline: u64 = runtime::get_named_arg(DEADLINE_RUNTIME_ARG_NAME);
+    let signature: Signature = runtime::get_named_arg(SIGNATURE_RUNTIME_ARG_NAME);
+
+    ERC20::default().permit(public_key, spender, amount, deadline, signature).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn nonces(){
+    let owner: Address = runtime::get_named_arg(OWNER_RUNTIME_ARG_NAME);
+    let nonce = ERC20::default().nonces(owner);
+    runtime::ret(CLValue::from_t(nonce).unwrap_or_revert());
+}
+
 #[no_mangle]
 pub extern "C" fn burn(){
     let owner: Address = runtime::get_named_arg(OWNER_RUNTIME_ARG_NAME);
@@ -14,6 +14,8 @@ pub const BALANCES_KEY_NAME: &str = "balances";
 pub const ALLOWANCES_KEY_NAME: &str = "allowances";
 /// Name of named-key for `total_supply`
 pub const TOTAL_SUPPLY_KEY_NAME: &str = "total_supply";
+/// Name of dictionary-key for `nonces`
+pub const NONCES_KEY_NAME: &str = "nonces";
 
 /// Name of `name` entry point.
 pub const NAME_ENTRY_POINT_NAME: &str = "name";
@@ -33,6 +35,10 @@ pub const ALLOWANCE_ENTRY_POINT_NAME: &str = "allowance";
 pub const TRANSFER_FROM_ENTRY_POINT_NAME: &str = "transfer_from";
 /// Name of `total_supply` entry point.
 pub const TOTAL_SUPPLY_ENTRY_POINT_NAME: &str = "total_supply";
+/// Name of `permit` entry point.
+pub const PERMIT_ENTRY_POINT_NAME: &str = "permit";
+/// Name of `nonces` entry point.
+pub const NONCES_ENTRY_POINT_NAME: &str = "nonces";
 
 /// Name of `address` runtime argument.
 pub const ADDRESS_RUNTIME_ARG_NAME: &str = "address";
@@ -52,3 +58,9 @@ pub const SYMBOL_RUNTIME_ARG_NAME: &str = "symbol";
 pub const DECIMALS_RUNTIME_ARG_NAME: &str = "decimals";
 /// Name of `total_supply` runtime argument.
 pub const TOTAL_SUPPLY_RUNTIME_ARG_NAME: &str = "total_supply";
+/// Name of `public_key` runtime argument.
+pub const PUBLIC_KEY_RUNTIME_ARG_NAME: &str = "public_key";
+/// Name of `deadline` runtime argument.
+pub const DEADLINE_RUNTIME_ARG_NAME: &str = "deadline";
+/// Name of `signature` runtime argument.
+pub const SIGNATURE_RUNTIME_ARG_NAME: &str = "signature";
@@ -2,17 +2,20 @@
 use alloc::{string::String, vec, vec::Vec};
 
 use casper_types::{
-    CLType, CLTyped, EntryPoint, EntryPointAccess, EntryPointType, EntryPoints, Parameter, U256,
+    CLType, CLTyped, EntryPoint, EntryPointAccess, EntryPointType, EntryPoints, Parameter,
+    PublicKey, Signature, U256,
 };
 
 use crate::{
     address::Address,
     constants::{
         ADDRESS_RUNTIME_ARG_NAME, ALLOWANCE_ENTRY_POINT_NAME, AMOUNT_RUNTIME_ARG_NAME,
-        APPROVE_ENTRY_POINT_NAME, BALANCE_OF_ENTRY_POINT_NAME, DECIMALS_ENTRY_POINT_NAME,
-        NAME_ENTRY_POINT_NAME, OWNER_RUNTIME_ARG_NAME, RECIPIENT_RUNTIME_ARG_NAME,
-        SPENDER_RUNTIME_ARG_NAME, SYMBOL_ENTRY_POINT_NAME, TOTAL_SUPPLY_ENTRY_POINT_NAME,
-        TRANSFER_ENTRY_POINT_NAME, TRANSFER_FROM_ENTRY_POINT_NAME,
+        APPROVE_ENTRY_POINT_NAME, BALANCE_OF_ENTRY_POINT_NAME, DEADLINE_RUNTIME_ARG_NAME,
+        DECIMALS_ENTRY_POINT_NAME, NAME_ENTRY_POINT_NAME, NONCES_ENTRY_POINT_NAME,
+        OWNER_RUNTIME_ARG_NAME, PERMIT_ENTRY_POINT_NAME, PUBLIC_KEY_RUNTIME_ARG_NAME,
+        RECIPIENT_RUNTIME_ARG_NAME, SIGNATURE_RUNTIME_ARG_NAME, SPENDER_RUNTIME_ARG_NAME,
+        SYMBOL_ENTRY_POINT_NAME, TOTAL_SUPPLY_ENTRY_POINT_NAME, TRANSFER_ENTRY_POINT_NAME,
+        TRANSFER_FROM_ENTRY_POINT_NAME,
     },
 };
 
@@ -142,6 +145,34 @@ pub fn decimals() -> EntryPoint {
     )
 }
 
+/// Returns the `permit` entry point.
+pub fn permit() -> EntryPoint {
+    EntryPoint::new(
+        String::from(PERMIT_ENTRY_POINT_NAME),
+        vec![
+            Parameter::new(PUBLIC_KEY_RUNTIME_ARG_NAME, PublicKey::cl_type()),
+            Parameter::new(SPENDER_RUNTIME_ARG_NAME, Address::cl_type()),
+            Parameter::new(AMOUNT_RUNTIME_ARG_NAME, U256::cl_type()),
+            Parameter::new(DEADLINE_RUNTIME_ARG_NAME, u64::cl_type()),
+            Parameter::new(SIGNATURE_RUNTIME_ARG_NAME, Signature::cl_type()),
+        ],
+        CLType::Unit,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    )
+}
+
+/// Returns the `nonces` entry point.
+pub fn nonces() -> EntryPoint {
+    EntryPoint::new(
+        String::from(NONCES_ENTRY_POINT_NAME),
+        vec![Parameter::new(OWNER_RUNTIME_ARG_NAME, Address::cl_type())],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    )
+}
+
 pub fn mint() -> EntryPoint {
     EntryPoint::new(
         String::from("mint"),
@@ -164,6 +195,8 @@ pub fn default() -> EntryPoints {
     entry_points.add_entry_point(approve());
     entry_points.add_entry_point(allowance());
     entry_points.add_entry_point(transfer_from());
+    entry_points.add_entry_point(permit());
+    entry_points.add_entry_point(nonces());
     entry_points.add_entry_point(mint());
     entry_points
 }
@@ -10,7 +10,7 @@ use casper_types::ApiError;
 /// return those via the [`Error::User`] variant or equivalently via the [`ApiError::User`]
 /// variant.
 ///
-/// Such a user error should be in the range `[0..(u16::MAX - 4)]` (i.e. [0, 65532]) to avoid
+/// Such a user error should be in the range `[0..(u16::MAX - 6)]` (i.e. [0, 65529]) to avoid
 /// conflicting with the other `Error` variants.
 #[derive(Debug)]
 pub enum Error {
@@ -22,6 +22,10 @@ pub enum Error {
     InsufficientAllowance,
     /// Operation would cause an integer overflow.
     Overflow,
+    /// Permit used after its deadline.
+    PermitExpired,
+    /// Permit not signed by the owner over its current nonce.
+    InvalidSignature,
     /// User error.
     User(u16),
 
@@ -32,6 +36,8 @@ const ERROR_INVALID_CONTEXT: u16 = u16::MAX;
 const ERROR_INSUFFICIENT_BALANCE: u16 = u16::MAX - 1;
 const ERROR_INSUFFICIENT_ALLOWANCE: u16 = u16::MAX - 2;
 const ERROR_OVERFLOW: u16 = u16::MAX - 3;
+const ERROR_PERMIT_EXPIRED: u16 = u16::MAX - 4;
+const ERROR_INVALID_SIGNATURE: u16 = u16::MAX - 5;
 
 impl From<Error> for ApiError {
     fn from(error: Error) -> Self {
@@ -40,6 +46,8 @@ impl From<Error> for ApiError {
             Error::InsufficientBalance => ERROR_INSUFFICIENT_BALANCE,
             Error::InsufficientAllowance => ERROR_INSUFFICIENT_ALLOWANCE,
             Error::Overflow => ERROR_OVERFLOW,
+            Error::PermitExpired => ERROR_PERMIT_EXPIRED,
+            Error::InvalidSignature => ERROR_INVALID_SIGNATURE,
             Error::User(user_error) => user_error,
             Error::UserAllowance(user_allowance) => user_allowance,
         };
@@ -22,6 +22,7 @@ pub mod constants;
 mod detail;
 pub mod entry_points;
 mod error;
+pub mod permit;
 mod total_supply;
 
 use alloc::string::{String, ToString};
@@ -32,12 +33,14 @@ use casper_contract::{
     contract_api::{runtime, storage},
     unwrap_or_revert::UnwrapOrRevert,
 };
-use casper_types::{contracts::NamedKeys, EntryPoints, Key, URef, U256};
+use casper_types::{
+    contracts::NamedKeys, crypto, BlockTime, EntryPoints, Key, PublicKey, Signature, URef, U256,
+};
 
 pub use address::Address;
 use constants::{
     ALLOWANCES_KEY_NAME, BALANCES_KEY_NAME, DECIMALS_KEY_NAME, ERC20_TOKEN_CONTRACT_KEY_NAME,
-    NAME_KEY_NAME, SYMBOL_KEY_NAME, TOTAL_SUPPLY_KEY_NAME,
+    NAME_KEY_NAME, NONCES_KEY_NAME, SYMBOL_KEY_NAME, TOTAL_SUPPLY_KEY_NAME,
 };
 pub use error::Error;
 
@@ -194,6 +197,41 @@ impl ERC20 {
         self.read_allowance(owner, spender)
     }
 
+    /// Returns the nonce the next permit of `owner` must be signed over.
+    pub fn nonces(&self, owner: Address) -> u64 {
+        permit::read_nonce_from(permit::nonces_uref(), owner)
+    }
+
+    /// Allows `spender` to transfer up to `amount` of the tokens of the account of `public_key`,
+    /// which signed the [`permit::permit_digest`] of the approval with its current nonce. Anyone can
+    /// submit the permit until the block time reaches `deadline`, in milliseconds.
+    pub fn permit(
+        &mut self,
+        public_key: PublicKey,
+        spender: Address,
+        amount: U256,
+        deadline: u64,
+        signature: Signature,
+    ) -> Result<(), Error> {
+        if runtime::get_blocktime() > BlockTime::new(deadline) {
+            return Err(Error::PermitExpired);
+        }
+        // The top of the call stack is this token.
+        let token = *detail::get_caller_address()?
+            .as_contract_package_hash()
+            .ok_or(Error::InvalidContext)?;
+        let owner = Address::from(public_key.to_account_hash());
+        let nonces_uref = permit::nonces_uref();
+        let nonce = permit::read_nonce_from(nonces_uref, owner);
+        let digest = permit::permit_digest(token, owner, spender, amount, nonce, deadline);
+        crypto::verify(digest, &signature, &public_key).map_err(|_| Error::InvalidSignature)?;
+
+        let next_nonce = nonce.checked_add(1).ok_or(Error::Overflow)?;
+        permit::write_nonce_to(nonces_uref, owner, next_nonce);
+        self.write_allowance(owner, spender, amount);
+        Ok(())
+    }
+
     /// Mints `amount` new tokens and adds them to `owner`'s balance and to the token total supply.
     ///
     /// # Security
@@ -255,6 +293,7 @@ impl ERC20 {
     ) -> Result<ERC20, Error> {
         let balances_uref = storage::new_dictionary(BALANCES_KEY_NAME).unwrap_or_revert();
         let allowances_uref = storage::new_dictionary(ALLOWANCES_KEY_NAME).unwrap_or_revert();
+        let nonces_uref = storage::new_dictionary(NONCES_KEY_NAME).unwrap_or_revert();
         // We need to hold on a RW access rights because tokens can be minted or burned.
         let total_supply_uref = storage::new_uref(initial_supply).into_read_write();
 
@@ -293,11 +332,18 @@ impl ERC20 {
             Key::from(allowances_uref)
         };
 
+        let nonces_dictionary_key = {
+            runtime::remove_key(NONCES_KEY_NAME);
+
+            Key::from(nonces_uref)
+        };
+
         named_keys.insert(NAME_KEY_NAME.to_string(), name_key);
         named_keys.insert(SYMBOL_KEY_NAME.to_string(), symbol_key);
         named_keys.insert(DECIMALS_KEY_NAME.to_string(), decimals_key);
         named_keys.insert(BALANCES_KEY_NAME.to_string(), balances_dictionary_key);
         named_keys.insert(ALLOWANCES_KEY_NAME.to_string(), allowances_dictionary_key);
+        named_keys.insert(NONCES_KEY_NAME.to_string(), nonces_dictionary_key);
         named_keys.insert(TOTAL_SUPPLY_KEY_NAME.to_string(), total_supply_key);
 
         let (contract_hash, _version) =
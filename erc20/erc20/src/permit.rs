@@ -0,0 +1,77 @@
+//! Implementation of signed permits.
+//!
+//! A permit lets `owner` approve `spender` with a signature made off-chain instead of a deploy of
+//! its own, in the way of EIP-2612. The signature covers the digest returned by [`permit_digest`],
+//! which commits to the token, the owner, the spender, the amount, the nonce of the owner and the
+//! deadline of the permit.
+use alloc::{string::String, vec::Vec};
+
+use casper_contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{bytesrepr::ToBytes, ContractPackageHash, URef, U256};
+
+use crate::{constants::NONCES_KEY_NAME, detail, Address};
+
+/// Domain tag prefixed to every permit, so that its signature can not be replayed as anything else.
+pub const PERMIT_DOMAIN: &str = "casper_erc20:permit";
+
+#[inline]
+pub(crate) fn nonces_uref() -> URef {
+    detail::get_uref(NONCES_KEY_NAME)
+}
+
+/// Creates a dictionary item key for an owner.
+fn make_dictionary_item_key(owner: Address) -> String {
+    let preimage = owner.to_bytes().unwrap_or_revert();
+    base64::encode(&preimage)
+}
+
+/// Reads the nonce of the next permit signed by `owner`.
+pub(crate) fn read_nonce_from(nonces_uref: URef, owner: Address) -> u64 {
+    let dictionary_item_key = make_dictionary_item_key(owner);
+    storage::dictionary_get(nonces_uref, &dictionary_item_key)
+        .unwrap_or_revert()
+        .unwrap_or_default()
+}
+
+/// Writes the nonce of the next permit signed by `owner`.
+pub(crate) fn write_nonce_to(nonces_uref: URef, owner: Address, nonce: u64) {
+    let dictionary_item_key = make_dictionary_item_key(owner);
+    storage::dictionary_put(nonces_uref, &dictionary_item_key, nonce);
+}
+
+/// Serializes the fields covered by a permit, prefixed with [`PERMIT_DOMAIN`].
+pub fn permit_preimage(
+    token: ContractPackageHash,
+    owner: Address,
+    spender: Address,
+    amount: U256,
+    nonce: u64,
+    deadline: u64,
+) -> Vec<u8> {
+    let mut preimage = Vec::new();
+    preimage.append(&mut PERMIT_DOMAIN.to_bytes().unwrap_or_revert());
+    preimage.append(&mut token.to_bytes().unwrap_or_revert());
+    preimage.append(&mut owner.to_bytes().unwrap_or_revert());
+    preimage.append(&mut spender.to_bytes().unwrap_or_revert());
+    preimage.append(&mut amount.to_bytes().unwrap_or_revert());
+    preimage.append(&mut nonce.to_bytes().unwrap_or_revert());
+    preimage.append(&mut deadline.to_bytes().unwrap_or_revert());
+    preimage
+}
+
+/// Returns the blake2b digest of the [`permit_preimage`] the owner signs.
+pub fn permit_digest(
+    token: ContractPackageHash,
+    owner: Address,
+    spender: Address,
+    amount: U256,
+    nonce: u64,
+    deadline: u64,
+) -> [u8; 32] {
+    runtime::blake2b(permit_preimage(
+        token, owner, spender, amount, nonce, deadline,
+    ))
+}
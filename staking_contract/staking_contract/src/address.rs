@@ -8,7 +8,7 @@ use casper_types::{
     bytesrepr::{self, FromBytes, ToBytes},
     CLType, CLTyped, ContractHash, ContractPackageHash, Key,
 };
-use core::convert::TryInto;
+use core::convert::{TryFrom, TryInto};
 
 /// An enum representing an [`AccountHash`] or a [`ContractPackageHash`].
 #[derive(PartialOrd, Ord, PartialEq, Eq, Hash, Clone, Copy, Debug)]
@@ -65,6 +65,20 @@ impl From<Address> for Key {
     }
 }
 
+impl TryFrom<Key> for Address {
+    type Error = crate::error::Error;
+
+    fn try_from(key: Key) -> Result<Self, Self::Error> {
+        match key {
+            Key::Account(account_hash) => Ok(Address::Account(account_hash)),
+            Key::Hash(raw_contract_package_hash) => Ok(Address::ContractPackage(
+                ContractPackageHash::new(raw_contract_package_hash),
+            )),
+            _ => Err(Self::Error::NeitherAccountHashNorNeitherContractPackageHash),
+        }
+    }
+}
+
 impl CLTyped for Address {
     fn cl_type() -> casper_types::CLType {
         CLType::Key
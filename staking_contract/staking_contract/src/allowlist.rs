@@ -0,0 +1,115 @@
+//! Merkle allowlists of private pools.
+//!
+//! Each leaf grants an address an allocation, the most it may have staked in the pool, and is the
+//! blake2b hash of [`leaf_preimage`]. Inner nodes hash their two children in ascending order, so a
+//! proof is the list of sibling hashes from the leaf up to the root. A node without a sibling is
+//! carried up unchanged.
+//!
+//! The hash function is passed in: contracts use `runtime::blake2b`, off-chain code any blake2b
+//! implementation with a 32 byte digest. [`MerkleTree`] builds roots and proofs off-chain.
+use crate::error::Error;
+use alloc::{vec, vec::Vec};
+use casper_types::{bytesrepr::ToBytes, Key, U256};
+
+/// Prefix of every leaf preimage, keeping leaves apart from inner nodes.
+pub const ALLOWLIST_DOMAIN: &str = "staking_contract:allowlist";
+
+pub type Hash = [u8; 32];
+
+/// Bytes hashed into the leaf granting `staker` an allocation of `allocation`.
+pub fn leaf_preimage(staker: &Key, allocation: U256) -> Result<Vec<u8>, Error> {
+    let mut preimage = ALLOWLIST_DOMAIN
+        .to_bytes()
+        .map_err(|_| Error::NotAllowlisted)?;
+    preimage.append(&mut staker.to_bytes().map_err(|_| Error::NotAllowlisted)?);
+    preimage.append(&mut allocation.to_bytes().map_err(|_| Error::NotAllowlisted)?);
+    Ok(preimage)
+}
+
+fn hash_pair<H: Fn(&[u8]) -> Hash>(a: &Hash, b: &Hash, hash: &H) -> Hash {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut bytes = Vec::with_capacity(64);
+    bytes.extend_from_slice(first);
+    bytes.extend_from_slice(second);
+    hash(&bytes)
+}
+
+/// Allocation claimed by a staker, with the proof of its leaf.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AllowlistProof {
+    pub allocation: U256,
+    pub proof: Vec<Hash>,
+}
+
+impl AllowlistProof {
+    /// Checks that the leaf granting `staker` this allocation is part of the tree of `root`.
+    pub fn verify<H: Fn(&[u8]) -> Hash>(
+        &self,
+        root: &Hash,
+        staker: &Key,
+        hash: H,
+    ) -> Result<(), Error> {
+        let leaf = hash(&leaf_preimage(staker, self.allocation)?);
+        let computed = self
+            .proof
+            .iter()
+            .fold(leaf, |node, sibling| hash_pair(&node, sibling, &hash));
+        if &computed == root {
+            Ok(())
+        } else {
+            Err(Error::NotAllowlisted)
+        }
+    }
+}
+
+/// Merkle tree of an allowlist, built off-chain to publish its root and hand out proofs.
+pub struct MerkleTree {
+    /// Levels of the tree, from the leaves up to the root.
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    /// Builds the tree of the allocations granted by `entries`, keeping their order.
+    pub fn new<H: Fn(&[u8]) -> Hash>(entries: &[(Key, U256)], hash: H) -> Result<Self, Error> {
+        let leaves = entries
+            .iter()
+            .map(|(staker, allocation)| Ok(hash(&leaf_preimage(staker, *allocation)?)))
+            .collect::<Result<Vec<Hash>, Error>>()?;
+        if leaves.is_empty() {
+            return Err(Error::NotAllowlisted);
+        }
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b, &hash),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+        Ok(MerkleTree { levels })
+    }
+
+    pub fn root(&self) -> Hash {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// Proof of the leaf of the entry at `index`, or `None` if there is no such entry.
+    pub fn proof(&self, index: usize) -> Option<Vec<Hash>> {
+        if index >= self.levels[0].len() {
+            return None;
+        }
+        let mut index = index;
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
@@ -0,0 +1,1064 @@
+#![no_std]
+#![no_main]
+
+#[cfg(not(target_arch = "wasm32"))]
+compile_error!("target arch should be wasm32: compile with '--target wasm32-unknown-unknown'");
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
+use casper_contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{
+    runtime_args, CLType, CLTyped, CLValue, ContractPackageHash, EntryPoint, EntryPointAccess,
+    EntryPointType, EntryPoints, Group, Key, Parameter, PublicKey, RuntimeArgs, Signature, URef,
+    U256,
+};
+use contract_utils::{ContractContext, OnChainContractStorage, RoleControl};
+use core::convert::TryFrom;
+use staking_contract::{
+    address::Address,
+    allowlist::Hash,
+    detail,
+    factory::CEP20STKFactory,
+    limit::StakeLimits,
+    lock::{self, Position},
+    penalty::{Penalty, PenaltyMode},
+    reward::RewardMode,
+    role::Role,
+    schedule::Schedule,
+    staking_contract::CEP20STK,
+};
+
+#[derive(Default)]
+struct Factory(OnChainContractStorage);
+
+impl ContractContext<OnChainContractStorage> for Factory {
+    fn storage(&self) -> &OnChainContractStorage {
+        &self.0
+    }
+}
+
+impl RoleControl<OnChainContractStorage> for Factory {}
+impl CEP20STK<OnChainContractStorage> for Factory {}
+impl CEP20STKFactory<OnChainContractStorage> for Factory {}
+
+/// Returns the factory with the pool given by the `pool_id` argument selected.
+fn pool() -> Factory {
+    let pool_id = runtime::get_named_arg::<u64>("pool_id");
+    let factory = Factory::default();
+    factory.select_pool(pool_id).unwrap_or_revert();
+    factory
+}
+
+#[no_mangle]
+pub extern "C" fn constructor() {
+    let stacking_contract_package_hash =
+        runtime::get_named_arg::<Key>("stacking_contract_package_hash");
+
+    #[allow(clippy::useless_conversion)]
+    runtime::put_key(
+        "stacking_contract_package_hash",
+        stacking_contract_package_hash.into(),
+    );
+
+    CEP20STKFactory::init(&mut Factory::default());
+}
+
+#[no_mangle]
+pub extern "C" fn create_pool() {
+    let name = runtime::get_named_arg::<String>("name");
+    let address = runtime::get_named_arg::<String>("address");
+    let staking_starts: u64 = runtime::get_named_arg::<u64>("staking_starts");
+    let staking_ends: u64 = runtime::get_named_arg::<u64>("staking_ends");
+    let withdraw_starts: u64 = runtime::get_named_arg::<u64>("withdraw_starts");
+    let withdraw_ends: u64 = runtime::get_named_arg::<u64>("withdraw_ends");
+    let staking_total: U256 = runtime::get_named_arg::<U256>("staking_total");
+    let erc20_contract_package_hash = runtime::get_named_arg::<Key>("erc20_contract_package_hash");
+    let reward_token_package_hash = runtime::get_named_arg::<Key>("reward_token_package_hash");
+    let reward_mode =
+        RewardMode::try_from(runtime::get_named_arg::<u8>("reward_mode")).unwrap_or_revert();
+    let lock_tiers = lock::lock_tiers(runtime::get_named_arg::<Vec<(u64, u64)>>("lock_tiers"))
+        .unwrap_or_revert();
+    let penalty = Penalty::new(
+        runtime::get_named_arg::<u64>("early_withdraw_penalty"),
+        PenaltyMode::try_from(runtime::get_named_arg::<u8>("penalty_mode")).unwrap_or_revert(),
+        runtime::get_named_arg::<Option<Key>>("treasury"),
+    )
+    .unwrap_or_revert();
+    let position_nft = runtime::get_named_arg::<Option<Key>>("position_nft");
+    let allowlist_root = runtime::get_named_arg::<Option<Hash>>("allowlist_root");
+
+    let ret = Factory::default()
+        .create_pool(
+            name,
+            address,
+            staking_starts,
+            staking_ends,
+            withdraw_starts,
+            withdraw_ends,
+            staking_total,
+            erc20_contract_package_hash,
+            reward_token_package_hash,
+            reward_mode,
+            lock_tiers,
+            penalty,
+            position_nft,
+            allowlist_root,
+        )
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn list_pools() {
+    let ret = Factory::default().list_pools();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn pool_count() {
+    let ret = Factory::default().pool_count();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn name() {
+    let ret = pool().name();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn address() {
+    let ret = pool().address();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn staking_starts() {
+    let ret = pool().staking_starts();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn staking_ends() {
+    let ret = pool().staking_ends();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw_starts() {
+    let ret = pool().withdraw_starts();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw_ends() {
+    let ret = pool().withdraw_ends();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn staking_total() {
+    let ret = pool().staking_total();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn amount_staked() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = pool().amount_staked(staker).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn positions() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = pool().positions(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn position() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let position_id = runtime::get_named_arg::<u64>("position_id");
+    let ret = pool().position(staker, position_id).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn position_count() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = pool().position_count(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn stake() {
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let staking_contract_package_hash =
+        runtime::get_named_arg::<String>("staking_contract_package_hash");
+    let lock_tier = runtime::get_named_arg::<u8>("lock_tier");
+    let referrer = detail::get_optional_named_arg::<Option<Key>>("referrer").flatten();
+    let ret = pool()
+        .stake(amount, staking_contract_package_hash, lock_tier, referrer)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn referrer() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = pool().referrer(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn referral_rewards() {
+    let referrer = runtime::get_named_arg::<Key>("referrer");
+    let ret = pool().referral_rewards(referrer);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn referral_rate() {
+    let ret = pool().referral_rate();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn set_referral_rate() {
+    let referral_rate = runtime::get_named_arg::<u64>("referral_rate");
+    pool().set_referral_rate(referral_rate).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn stake_for() {
+    let beneficiary = runtime::get_named_arg::<Address>("beneficiary");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let staking_contract_package_hash =
+        runtime::get_named_arg::<String>("staking_contract_package_hash");
+    let lock_tier = runtime::get_named_arg::<u8>("lock_tier");
+    let ret = pool()
+        .stake_for(
+            beneficiary,
+            amount,
+            staking_contract_package_hash,
+            lock_tier,
+        )
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn stake_with_permit() {
+    let public_key = runtime::get_named_arg::<PublicKey>("public_key");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let deadline = runtime::get_named_arg::<u64>("deadline");
+    let signature = runtime::get_named_arg::<Signature>("signature");
+    let terms_signature = runtime::get_named_arg::<Signature>("terms_signature");
+    let staking_contract_package_hash =
+        runtime::get_named_arg::<String>("staking_contract_package_hash");
+    let lock_tier = runtime::get_named_arg::<u8>("lock_tier");
+    let ret = pool()
+        .stake_with_permit(
+            public_key,
+            amount,
+            deadline,
+            signature,
+            terms_signature,
+            staking_contract_package_hash,
+            lock_tier,
+        )
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn stake_with_proof() {
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let staking_contract_package_hash =
+        runtime::get_named_arg::<String>("staking_contract_package_hash");
+    let lock_tier = runtime::get_named_arg::<u8>("lock_tier");
+    let allocation = runtime::get_named_arg::<U256>("allocation");
+    let proof = runtime::get_named_arg::<Vec<Hash>>("proof");
+    let referrer = detail::get_optional_named_arg::<Option<Key>>("referrer").flatten();
+    let ret = pool()
+        .stake_with_proof(
+            amount,
+            staking_contract_package_hash,
+            lock_tier,
+            allocation,
+            proof,
+            referrer,
+        )
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn allowlist_root() {
+    let ret = pool().allowlist_root();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn set_allowlist_root() {
+    let root = runtime::get_named_arg::<Option<Hash>>("root");
+    pool().set_allowlist_root(root).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw() {
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let ret = pool().withdraw(amount).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw_position() {
+    let position_id = runtime::get_named_arg::<u64>("position_id");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let ret = pool()
+        .withdraw_position(position_id, amount)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw_nft() {
+    let token_id = runtime::get_named_arg::<u64>("token_id");
+    let ret = pool().withdraw_nft(token_id).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn position_token_id() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let position_id = runtime::get_named_arg::<u64>("position_id");
+    let ret = pool().position_token_id(staker, position_id);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn position_nft() {
+    let ret = pool().position_nft();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn claim_rewards() {
+    let ret = pool().claim_rewards().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn claim_rewards_nft() {
+    let token_id = runtime::get_named_arg::<u64>("token_id");
+    let ret = pool().claim_rewards_nft(token_id).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw_for() {
+    let staker = runtime::get_named_arg::<Address>("staker");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let ret = pool().withdraw_for(staker, amount).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw_position_for() {
+    let staker = runtime::get_named_arg::<Address>("staker");
+    let position_id = runtime::get_named_arg::<u64>("position_id");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let ret = pool()
+        .withdraw_position_for(staker, position_id, amount)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn claim_rewards_for() {
+    let staker = runtime::get_named_arg::<Address>("staker");
+    let ret = pool().claim_rewards_for(staker).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn approve_operator() {
+    let operator = runtime::get_named_arg::<Key>("operator");
+    pool().approve_operator(operator).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn revoke_operator() {
+    let operator = runtime::get_named_arg::<Key>("operator");
+    pool().revoke_operator(operator).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn is_operator() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let operator = runtime::get_named_arg::<Key>("operator");
+    let ret = pool().is_operator(staker, operator);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn set_payout_destination() {
+    let destination = runtime::get_named_arg::<Option<Key>>("destination");
+    pool()
+        .set_payout_destination(destination)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn payout_destination() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = pool().payout_destination(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn lock_tiers() {
+    let ret: Vec<(u64, u64)> = pool().lock_tiers().into_iter().map(Into::into).collect();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn penalty() {
+    let penalty = pool().penalty();
+    let ret = (penalty.rate, penalty.mode as u8, penalty.treasury);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn claimed_rewards() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = pool().claimed_rewards(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn reward_token_package_hash() {
+    let ret = pool().reward_token_package_hash();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn paused() {
+    let ret = pool().paused();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn phase() {
+    let ret = pool().phase() as u8;
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn reschedule() {
+    let schedule = Schedule {
+        staking_starts: runtime::get_named_arg::<u64>("staking_starts"),
+        staking_ends: runtime::get_named_arg::<u64>("staking_ends"),
+        withdraw_starts: runtime::get_named_arg::<u64>("withdraw_starts"),
+        withdraw_ends: runtime::get_named_arg::<u64>("withdraw_ends"),
+    };
+    pool().reschedule(schedule).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn min_stake() {
+    let ret = pool().stake_limits().min_stake;
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn max_stake_per_address() {
+    let ret = pool().stake_limits().max_stake_per_address;
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn set_stake_limits() {
+    let stake_limits = StakeLimits {
+        min_stake: runtime::get_named_arg::<U256>("min_stake"),
+        max_stake_per_address: runtime::get_named_arg::<Option<U256>>("max_stake_per_address"),
+    };
+    pool().set_stake_limits(stake_limits).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn stake_cap() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = pool().stake_cap(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn set_stake_cap() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let cap = runtime::get_named_arg::<Option<U256>>("cap");
+    pool().set_stake_cap(staker, cap).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn pause() {
+    pool().pause().unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn unpause() {
+    pool().unpause().unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn emergency_withdraw() {
+    let ret = pool().emergency_withdraw().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn emergency_withdraw_nft() {
+    let token_id = runtime::get_named_arg::<u64>("token_id");
+    let ret = pool().emergency_withdraw_nft(token_id).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn dust() {
+    let ret = pool().dust();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn sweep_dust() {
+    let ret = pool().sweep_dust().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn compound() {
+    let ret = pool().compound().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn shares_of() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = pool().shares_of(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn price_per_share() {
+    let ret = pool().price_per_share().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn redeem() {
+    let shares = runtime::get_named_arg::<U256>("shares");
+    let ret = pool().redeem(shares).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn add_reward() {
+    let reward_amount = runtime::get_named_arg::<U256>("reward_amount");
+    let withdrawable_amount = runtime::get_named_arg::<U256>("withdrawable_amount");
+    let ret = pool()
+        .add_reward(reward_amount, withdrawable_amount)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_current_reward() {
+    let ret = pool().reward_balance();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn staker_reward() {
+    let staker_address = runtime::get_named_arg::<Key>("staker_address");
+    let ret = pool().staker_reward(staker_address).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn grant_role() {
+    let role = Role::try_from(runtime::get_named_arg::<u8>("role")).unwrap_or_revert();
+    let account = runtime::get_named_arg::<Key>("account");
+    Factory::default()
+        .grant_role(role, account)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn revoke_role() {
+    let role = Role::try_from(runtime::get_named_arg::<u8>("role")).unwrap_or_revert();
+    let account = runtime::get_named_arg::<Key>("account");
+    Factory::default()
+        .revoke_role(role, account)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn renounce_role() {
+    let role = Role::try_from(runtime::get_named_arg::<u8>("role")).unwrap_or_revert();
+    Factory::default().renounce_role(role).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn has_role() {
+    let role = Role::try_from(runtime::get_named_arg::<u8>("role")).unwrap_or_revert();
+    let account = runtime::get_named_arg::<Key>("account");
+    let ret = RoleControl::has_role(&Factory::default(), role as u8, account);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let (contract_hash, _) = storage::new_contract(
+        get_entry_points(),
+        None,
+        Some(String::from("staking_factory_package_hash")),
+        None,
+    );
+
+    let package_hash: ContractPackageHash = ContractPackageHash::new(
+        runtime::get_key("staking_factory_package_hash")
+            .unwrap_or_revert()
+            .into_hash()
+            .unwrap_or_revert(),
+    );
+
+    let package_hash_key: Key = package_hash.into();
+
+    let constructor_args = runtime_args! {
+        "stacking_contract_package_hash" => package_hash_key,
+    };
+
+    let constructor_access: URef =
+        storage::create_contract_user_group(package_hash, "constructor", 1, Default::default())
+            .unwrap_or_revert()
+            .pop()
+            .unwrap_or_revert();
+
+    let _: () = runtime::call_contract(contract_hash, "constructor", constructor_args);
+
+    let mut urefs = BTreeSet::new();
+    urefs.insert(constructor_access);
+    storage::remove_contract_user_group_urefs(package_hash, "constructor", urefs)
+        .unwrap_or_revert();
+
+    runtime::put_key("staking_factory_contract_hash", contract_hash.into());
+    runtime::put_key(
+        &format!("{contract_hash}_factory_contract_hash_wrapped"),
+        storage::new_uref(contract_hash).into(),
+    );
+}
+
+/// Prepends the `pool_id` parameter shared by every pool-scoped entry point.
+fn pool_params(params: Vec<Parameter>) -> Vec<Parameter> {
+    let mut ret = vec![Parameter::new("pool_id", u64::cl_type())];
+    ret.extend(params);
+    ret
+}
+
+fn pool_entry_point(name: &str, params: Vec<Parameter>, ret: CLType) -> EntryPoint {
+    EntryPoint::new(
+        name,
+        pool_params(params),
+        ret,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    )
+}
+
+fn get_entry_points() -> EntryPoints {
+    let mut entry_points = EntryPoints::new();
+    entry_points.add_entry_point(EntryPoint::new(
+        "constructor",
+        vec![Parameter::new(
+            "stacking_contract_package_hash",
+            Key::cl_type(),
+        )],
+        <()>::cl_type(),
+        EntryPointAccess::Groups(vec![Group::new("constructor")]),
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "create_pool",
+        vec![
+            Parameter::new("name", String::cl_type()),
+            Parameter::new("address", String::cl_type()),
+            Parameter::new("staking_starts", u64::cl_type()),
+            Parameter::new("staking_ends", u64::cl_type()),
+            Parameter::new("withdraw_starts", u64::cl_type()),
+            Parameter::new("withdraw_ends", u64::cl_type()),
+            Parameter::new("staking_total", U256::cl_type()),
+            Parameter::new("erc20_contract_package_hash", Key::cl_type()),
+            Parameter::new("reward_token_package_hash", Key::cl_type()),
+            Parameter::new("reward_mode", u8::cl_type()),
+            Parameter::new("lock_tiers", Vec::<(u64, u64)>::cl_type()),
+            Parameter::new("early_withdraw_penalty", u64::cl_type()),
+            Parameter::new("penalty_mode", u8::cl_type()),
+            Parameter::new("treasury", Option::<Key>::cl_type()),
+            Parameter::new("position_nft", Option::<Key>::cl_type()),
+            Parameter::new("allowlist_root", Option::<Hash>::cl_type()),
+        ],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "list_pools",
+        vec![],
+        Vec::<u64>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "pool_count",
+        vec![],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(pool_entry_point("name", vec![], String::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("address", vec![], String::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("staking_starts", vec![], u64::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("staking_ends", vec![], u64::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("withdraw_starts", vec![], u64::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("withdraw_ends", vec![], u64::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("staking_total", vec![], U256::cl_type()));
+    entry_points.add_entry_point(pool_entry_point(
+        "amount_staked",
+        vec![Parameter::new("staker", Key::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "stake",
+        vec![
+            Parameter::new("amount", U256::cl_type()),
+            Parameter::new("staking_contract_package_hash", String::cl_type()),
+            Parameter::new("lock_tier", u8::cl_type()),
+            Parameter::new("referrer", Option::<Key>::cl_type()),
+        ],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "referrer",
+        vec![Parameter::new("staker", Key::cl_type())],
+        Option::<Key>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "referral_rewards",
+        vec![Parameter::new("referrer", Key::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point("referral_rate", vec![], u64::cl_type()));
+    entry_points.add_entry_point(pool_entry_point(
+        "set_referral_rate",
+        vec![Parameter::new("referral_rate", u64::cl_type())],
+        <()>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "stake_for",
+        vec![
+            Parameter::new("beneficiary", Key::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+            Parameter::new("staking_contract_package_hash", String::cl_type()),
+            Parameter::new("lock_tier", u8::cl_type()),
+        ],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "stake_with_permit",
+        vec![
+            Parameter::new("public_key", PublicKey::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+            Parameter::new("deadline", u64::cl_type()),
+            Parameter::new("signature", Signature::cl_type()),
+            Parameter::new("terms_signature", Signature::cl_type()),
+            Parameter::new("staking_contract_package_hash", String::cl_type()),
+            Parameter::new("lock_tier", u8::cl_type()),
+        ],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "stake_with_proof",
+        vec![
+            Parameter::new("amount", U256::cl_type()),
+            Parameter::new("staking_contract_package_hash", String::cl_type()),
+            Parameter::new("lock_tier", u8::cl_type()),
+            Parameter::new("allocation", U256::cl_type()),
+            Parameter::new("proof", Vec::<Hash>::cl_type()),
+            Parameter::new("referrer", Option::<Key>::cl_type()),
+        ],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "allowlist_root",
+        vec![],
+        Option::<Hash>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "set_allowlist_root",
+        vec![Parameter::new("root", Option::<Hash>::cl_type())],
+        <()>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "withdraw",
+        vec![Parameter::new("amount", U256::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "positions",
+        vec![Parameter::new("staker", Key::cl_type())],
+        Vec::<Position>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "position",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("position_id", u64::cl_type()),
+        ],
+        Position::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "position_count",
+        vec![Parameter::new("staker", Key::cl_type())],
+        u64::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "withdraw_position",
+        vec![
+            Parameter::new("position_id", u64::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+        ],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "add_reward",
+        vec![
+            Parameter::new("reward_amount", U256::cl_type()),
+            Parameter::new("withdrawable_amount", U256::cl_type()),
+        ],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "withdraw_nft",
+        vec![Parameter::new("token_id", u64::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "position_token_id",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("position_id", u64::cl_type()),
+        ],
+        Option::<u64>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "position_nft",
+        vec![],
+        Option::<Key>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point("claim_rewards", vec![], U256::cl_type()));
+    entry_points.add_entry_point(pool_entry_point(
+        "claim_rewards_nft",
+        vec![Parameter::new("token_id", u64::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "withdraw_for",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+        ],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "withdraw_position_for",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("position_id", u64::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+        ],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "claim_rewards_for",
+        vec![Parameter::new("staker", Key::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "approve_operator",
+        vec![Parameter::new("operator", Key::cl_type())],
+        <()>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "revoke_operator",
+        vec![Parameter::new("operator", Key::cl_type())],
+        <()>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "is_operator",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("operator", Key::cl_type()),
+        ],
+        bool::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "set_payout_destination",
+        vec![Parameter::new("destination", Option::<Key>::cl_type())],
+        <()>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "payout_destination",
+        vec![Parameter::new("staker", Key::cl_type())],
+        Option::<Key>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "lock_tiers",
+        vec![],
+        Vec::<(u64, u64)>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "penalty",
+        vec![],
+        <(u64, u8, Option<Key>)>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "claimed_rewards",
+        vec![Parameter::new("staker", Key::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "reward_token_package_hash",
+        vec![],
+        Key::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "get_current_reward",
+        vec![],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "staker_reward",
+        vec![Parameter::new("staker_address", Key::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point("paused", vec![], bool::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("phase", vec![], u8::cl_type()));
+    entry_points.add_entry_point(pool_entry_point(
+        "reschedule",
+        vec![
+            Parameter::new("staking_starts", u64::cl_type()),
+            Parameter::new("staking_ends", u64::cl_type()),
+            Parameter::new("withdraw_starts", u64::cl_type()),
+            Parameter::new("withdraw_ends", u64::cl_type()),
+        ],
+        <()>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point("min_stake", vec![], U256::cl_type()));
+    entry_points.add_entry_point(pool_entry_point(
+        "max_stake_per_address",
+        vec![],
+        Option::<U256>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "set_stake_limits",
+        vec![
+            Parameter::new("min_stake", U256::cl_type()),
+            Parameter::new("max_stake_per_address", Option::<U256>::cl_type()),
+        ],
+        <()>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "stake_cap",
+        vec![Parameter::new("staker", Key::cl_type())],
+        Option::<U256>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "set_stake_cap",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("cap", Option::<U256>::cl_type()),
+        ],
+        <()>::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point("pause", vec![], <()>::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("unpause", vec![], <()>::cl_type()));
+    entry_points.add_entry_point(pool_entry_point(
+        "emergency_withdraw",
+        vec![],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point(
+        "emergency_withdraw_nft",
+        vec![Parameter::new("token_id", u64::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point("dust", vec![], U256::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("sweep_dust", vec![], U256::cl_type()));
+    entry_points.add_entry_point(pool_entry_point("compound", vec![], U256::cl_type()));
+    entry_points.add_entry_point(pool_entry_point(
+        "shares_of",
+        vec![Parameter::new("staker", Key::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(pool_entry_point("price_per_share", vec![], U256::cl_type()));
+    entry_points.add_entry_point(pool_entry_point(
+        "redeem",
+        vec![Parameter::new("shares", U256::cl_type())],
+        U256::cl_type(),
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "grant_role",
+        vec![
+            Parameter::new("role", u8::cl_type()),
+            Parameter::new("account", Key::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "revoke_role",
+        vec![
+            Parameter::new("role", u8::cl_type()),
+            Parameter::new("account", Key::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "renounce_role",
+        vec![Parameter::new("role", u8::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "has_role",
+        vec![
+            Parameter::new("role", u8::cl_type()),
+            Parameter::new("account", Key::cl_type()),
+        ],
+        bool::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points
+}
@@ -1,20 +1,57 @@
+use crate::allowlist::Hash;
 use crate::error::Error;
 use crate::event::StakingContractEvent;
+use crate::limit::StakeLimits;
+use crate::lock::{LockTier, Position, MAX_LIVE_POSITIONS};
+use crate::penalty::{Penalty, PenaltyMode};
+use crate::reward::RewardMode;
+use crate::safe_math::SafeMath;
 use alloc::{
-    collections::BTreeMap,
+    format,
     string::{String, ToString},
+    vec,
     vec::Vec,
 };
 use casper_contract::{
-    contract_api::{runtime::get_call_stack, storage},
+    contract_api::{
+        runtime::{self, get_call_stack},
+        storage,
+    },
+    ext_ffi,
     unwrap_or_revert::UnwrapOrRevert,
 };
-use casper_types::{system::CallStackElement, ContractPackageHash, Key, URef, U256};
-use contract_utils::{get_key, key_to_str, set_key, Dict};
-use core::convert::TryInto;
+use casper_types::{
+    api_error,
+    bytesrepr::{FromBytes, ToBytes},
+    system::CallStackElement,
+    CLType, CLTyped, CLValue, ContractPackageHash, Key, URef, U256,
+};
+use contract_utils::{get_key, key_and_value_to_str, key_to_str, keys_to_str, set_key, Dict};
+use core::convert::TryFrom;
 
 const AMOUNT_STAKED_BY_ADDRESS_DICT: &str = "amount_staked_by_addresses_dict";
-const CONTRACT_PACKAGE_HASH: &str = "contract_package_hash";
+const WEIGHTED_STAKES_DICT: &str = "weighted_stakes";
+const POSITIONS_DICT: &str = "positions";
+const POSITION_COUNTS_DICT: &str = "position_counts";
+const LIVE_POSITIONS_DICT: &str = "live_positions";
+const REWARD_DEBT_DICT: &str = "reward_debt";
+const PENDING_REWARDS_DICT: &str = "pending_rewards";
+const PREPAID_REWARDS_DICT: &str = "prepaid_rewards";
+const CLAIMED_REWARDS_DICT: &str = "claimed_rewards";
+const REWARD_REMAINDERS_DICT: &str = "reward_remainders";
+const POSITION_TOKEN_IDS_DICT: &str = "position_token_ids";
+const TOKEN_POSITIONS_DICT: &str = "token_positions";
+const OPERATORS_DICT: &str = "operators";
+const PAYOUT_DESTINATIONS_DICT: &str = "payout_destinations";
+const STAKE_CAPS_DICT: &str = "stake_caps";
+const REFERRERS_DICT: &str = "referrers";
+const REFERRAL_REWARDS_DICT: &str = "referral_rewards";
+const EVENTS_DICT: &str = "__events";
+const EVENTS_LENGTH: &str = "__events_length";
+const EVENTS_SCHEMA: &str = "__events_schema";
+const EVENTS_CES_VERSION: &str = "__events_ces_version";
+const CES_VERSION: &str = "0.1.0";
+const POOL_NAMESPACE_PREFIX: &str = "pool";
 
 pub const NAME: &str = "name";
 pub const ADDRESS: &str = "address";
@@ -29,149 +66,935 @@ pub const EARLY_WITHDRAW_REWARD: &str = "early_withdraw_reward";
 pub const STAKED_TOTAL: &str = "staked_total";
 pub const REWARD_BALANCE: &str = "reward_balance";
 pub const STAKED_BALANCE: &str = "staked_balance";
+pub const ERC20_CONTRACT_PACKAGE_HASH: &str = "erc20_contract_package_hash";
+pub const REWARD_TOKEN_PACKAGE_HASH: &str = "reward_token_package_hash";
+pub const REWARD_MODE: &str = "reward_mode";
+pub const REWARD_RATE: &str = "reward_rate";
+pub const REWARD_PER_TOKEN_STORED: &str = "reward_per_token_stored";
+pub const LAST_UPDATE_TIME: &str = "last_update_time";
+pub const PAUSED: &str = "paused";
+pub const LOCK_TIERS: &str = "lock_tiers";
+pub const WEIGHTED_BALANCE: &str = "weighted_balance";
+pub const WEIGHTED_TOTAL: &str = "weighted_total";
+pub const EARLY_WITHDRAW_PENALTY: &str = "early_withdraw_penalty";
+pub const PENALTY_MODE: &str = "penalty_mode";
+pub const TREASURY: &str = "treasury";
+pub const DISTRIBUTED_REWARDS: &str = "distributed_rewards";
+pub const OUTSTANDING_STAKE: &str = "outstanding_stake";
+pub const VAULT_ASSETS: &str = "vault_assets";
+pub const POSITION_NFT: &str = "position_nft";
+pub const MIN_STAKE: &str = "min_stake";
+pub const MAX_STAKE_PER_ADDRESS: &str = "max_stake_per_address";
+pub const ALLOWLIST_ROOT: &str = "allowlist_root";
+pub const REFERRAL_RATE: &str = "referral_rate";
+pub const SCHEMA_VERSION: &str = "schema_version";
+
+/// Storage layout version written by this build, bumped whenever a migration step is added to
+/// [`crate::staking_contract::CEP20STK::migrate`].
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Pool whose storage namespace is used by the accessors below. `None` means the contract hosts a
+/// single pool and its state lives directly in named keys.
+static mut NAMESPACE: Option<u64> = None;
+
+/// Selects the pool namespace used by every subsequent read and write of the current execution.
+pub fn set_namespace(pool_id: Option<u64>) {
+    unsafe { NAMESPACE = pool_id }
+}
+
+pub fn namespace() -> Option<u64> {
+    unsafe { NAMESPACE }
+}
+
+/// Creates the dictionary holding the scalar state of the pool `pool_id`.
+pub fn init_namespace(pool_id: u64) {
+    Dict::init(&namespace_dict_name(pool_id));
+}
 
+fn namespace_dict_name(pool_id: u64) -> String {
+    format!("{POOL_NAMESPACE_PREFIX}_{pool_id}")
+}
+
+/// Returns the name under which the dictionary `name` of the selected pool is stored.
+fn namespaced(name: &str) -> String {
+    match namespace() {
+        Some(pool_id) => format!("{name}_{pool_id}"),
+        None => name.to_string(),
+    }
+}
+
+/// Opens the dictionary `name` of the selected pool.
+pub fn dict(name: &str) -> Dict {
+    Dict::instance(&namespaced(name))
+}
+
+/// Creates the dictionary `name` for the selected pool.
+pub fn init_dict(name: &str) {
+    Dict::init(&namespaced(name));
+}
+
+fn read<T: FromBytes + CLTyped>(name: &str) -> Option<T> {
+    match namespace() {
+        Some(pool_id) => Dict::instance(&namespace_dict_name(pool_id)).get(name),
+        None => get_key(name),
+    }
+}
+
+fn write<T: ToBytes + CLTyped>(name: &str, value: T) {
+    match namespace() {
+        Some(pool_id) => Dict::instance(&namespace_dict_name(pool_id)).set(name, value),
+        None => set_key(name, value),
+    }
+}
+
+/// Stakes of every staker.
+///
+/// Each stake is recorded as a [`Position`] stored under its staker and index, alongside the number
+/// of positions opened by the staker, the total amount staked and its reward weight, boosted by the
+/// multipliers of the lock tiers. The indexes of the positions still holding tokens are kept in a
+/// list per staker, so that fully withdrawn positions are never read again.
 pub struct StakedTokens {
     addresses_staked_dict: Dict,
+    weighted_stakes_dict: Dict,
+    positions_dict: Dict,
+    position_counts_dict: Dict,
+    live_positions_dict: Dict,
 }
 
 impl StakedTokens {
     pub fn instance() -> StakedTokens {
         StakedTokens {
-            addresses_staked_dict: Dict::instance(AMOUNT_STAKED_BY_ADDRESS_DICT),
+            addresses_staked_dict: dict(AMOUNT_STAKED_BY_ADDRESS_DICT),
+            weighted_stakes_dict: dict(WEIGHTED_STAKES_DICT),
+            positions_dict: dict(POSITIONS_DICT),
+            position_counts_dict: dict(POSITION_COUNTS_DICT),
+            live_positions_dict: dict(LIVE_POSITIONS_DICT),
         }
     }
 
     pub fn init() {
-        Dict::init(AMOUNT_STAKED_BY_ADDRESS_DICT);
+        init_dict(AMOUNT_STAKED_BY_ADDRESS_DICT);
+        init_dict(WEIGHTED_STAKES_DICT);
+        init_dict(POSITIONS_DICT);
+        init_dict(POSITION_COUNTS_DICT);
+        init_dict(LIVE_POSITIONS_DICT);
     }
 
     pub fn get_amount_staked_by_address(&self, address: &Key) -> Option<U256> {
         self.addresses_staked_dict.get(&key_to_str(address))
     }
 
-    pub fn add_stake(&self, owner: &Key, amount: &U256) {
+    /// Reward weight of the stake of `address`.
+    pub fn weighted_stake(&self, address: &Key) -> U256 {
+        self.weighted_stakes_dict
+            .get(&key_to_str(address))
+            .unwrap_or_default()
+    }
+
+    /// Number of positions ever opened by `address`, including the fully withdrawn ones.
+    pub fn position_count(&self, address: &Key) -> u64 {
+        self.position_counts_dict
+            .get(&key_to_str(address))
+            .unwrap_or_default()
+    }
+
+    /// Position `id` of `address`.
+    pub fn position(&self, address: &Key, id: u64) -> Option<Position> {
+        self.positions_dict.get(&key_and_value_to_str(address, &id))
+    }
+
+    /// Positions of `address` still holding tokens, oldest first.
+    pub fn positions(&self, address: &Key) -> Vec<Position> {
+        self.live_position_ids(address)
+            .into_iter()
+            .filter_map(|id| self.position(address, id))
+            .collect()
+    }
+
+    /// Indexes of the positions of `address` still holding tokens, in ascending order.
+    fn live_position_ids(&self, address: &Key) -> Vec<u64> {
+        self.live_positions_dict
+            .get(&key_to_str(address))
+            .unwrap_or_default()
+    }
+
+    fn set_live_position_ids(&self, address: &Key, ids: Vec<u64>) {
+        self.live_positions_dict.set(&key_to_str(address), ids);
+    }
+
+    /// Stores `position`, dropping it from the live positions of `address` once it is empty.
+    fn set_position(&self, address: &Key, position: Position) {
+        if position.amount.is_zero() {
+            let mut ids = self.live_position_ids(address);
+            if let Some(index) = ids.iter().position(|id| *id == position.id) {
+                ids.remove(index);
+                self.set_live_position_ids(address, ids);
+            }
+        }
+        self.positions_dict
+            .set(&key_and_value_to_str(address, &position.id), position);
+    }
+
+    /// Opens a new position of `amount` for `owner` and returns it. Fails with `TooManyPositions`
+    /// if `owner` already holds [`MAX_LIVE_POSITIONS`] positions.
+    pub fn add_stake(
+        &self,
+        owner: &Key,
+        amount: U256,
+        timestamp: u64,
+        lock_tier: u8,
+        unlock_time: u64,
+        weight: U256,
+    ) -> Result<Position, Error> {
+        let mut ids = self.live_position_ids(owner);
+        if !amount.is_zero() && ids.len() >= MAX_LIVE_POSITIONS {
+            return Err(Error::TooManyPositions);
+        }
         let new_amount = if let Some(staked_amount) = self.get_amount_staked_by_address(owner) {
-            staked_amount + amount
+            staked_amount.safe_add(amount)?
         } else {
-            *amount
+            amount
+        };
+        let new_weight = self.weighted_stake(owner).safe_add(weight)?;
+        let position = Position {
+            id: self.position_count(owner),
+            amount,
+            timestamp,
+            lock_tier,
+            unlock_time,
         };
+
         self.addresses_staked_dict
             .set(&key_to_str(owner), new_amount);
+        self.weighted_stakes_dict
+            .set(&key_to_str(owner), new_weight);
+        self.position_counts_dict
+            .set(&key_to_str(owner), position.id.safe_add(1)?);
+        if !amount.is_zero() {
+            ids.push(position.id);
+            self.set_live_position_ids(owner, ids);
+        }
+        self.set_position(owner, position);
+        Ok(position)
+    }
+
+    /// Withdraws `amount` from the positions of `owner` unlocked at `now`, oldest first, and
+    /// returns the reward weight withdrawn along with it.
+    pub fn withdraw_stake(
+        &self,
+        owner: &Key,
+        amount: &U256,
+        now: u64,
+        lock_tiers: &[LockTier],
+    ) -> Result<U256, Error> {
+        let positions: Vec<Position> = self
+            .positions(owner)
+            .into_iter()
+            .filter(|position| !position.is_locked(now))
+            .collect();
+        let unlocked = positions
+            .iter()
+            .try_fold(U256::zero(), |unlocked, position| {
+                unlocked.safe_add(position.amount)
+            })?;
+        if unlocked < *amount {
+            return Err(Error::StakeLocked);
+        }
+
+        let mut remaining = *amount;
+        let mut weight = U256::zero();
+        for position in positions {
+            if remaining.is_zero() {
+                break;
+            }
+            let withdrawn = position.amount.min(remaining);
+            remaining = remaining.safe_sub(withdrawn)?;
+            weight = weight
+                .safe_add(self.take_from_position(owner, position, withdrawn, lock_tiers)?)?;
+        }
+        self.take_from_stake(owner, amount, weight)
     }
 
-    pub fn withdraw_stake(&self, owner: &Key, amount: &U256) -> Result<(), Error> {
+    /// Withdraws `amount` from the position `id` of `owner`, which must be unlocked at `now`, and
+    /// returns the reward weight withdrawn along with it.
+    pub fn withdraw_position(
+        &self,
+        owner: &Key,
+        id: u64,
+        amount: &U256,
+        now: u64,
+        lock_tiers: &[LockTier],
+    ) -> Result<U256, Error> {
+        let position = self.position(owner, id).ok_or(Error::UnknownPosition)?;
+        if position.is_locked(now) {
+            return Err(Error::StakeLocked);
+        }
+        if position.amount < *amount {
+            return Err(Error::NotRequiredStake);
+        }
+        let weight = self.take_from_position(owner, position, *amount, lock_tiers)?;
+        self.take_from_stake(owner, amount, weight)
+    }
+
+    /// Withdraws the whole position `id` of `owner` regardless of its lock, and returns the reward
+    /// weight withdrawn along with it.
+    pub fn release_position(
+        &self,
+        owner: &Key,
+        id: u64,
+        lock_tiers: &[LockTier],
+    ) -> Result<U256, Error> {
+        let position = self.position(owner, id).ok_or(Error::UnknownPosition)?;
+        let weight = self.take_from_position(owner, position, position.amount, lock_tiers)?;
+        self.take_from_stake(owner, &position.amount, weight)
+    }
+
+    /// Removes `amount` from `position` and returns its reward weight.
+    fn take_from_position(
+        &self,
+        owner: &Key,
+        mut position: Position,
+        amount: U256,
+        lock_tiers: &[LockTier],
+    ) -> Result<U256, Error> {
+        position.amount = position.amount.safe_sub(amount)?;
+        self.set_position(owner, position);
+        lock_tiers
+            .get(position.lock_tier as usize)
+            .ok_or(Error::InvalidLockTier)?
+            .weight(amount)
+    }
+
+    /// Removes `amount` worth `weight` from the totals of `owner` and returns the weight actually
+    /// removed.
+    fn take_from_stake(&self, owner: &Key, amount: &U256, mut weight: U256) -> Result<U256, Error> {
         let staked_amount = self
             .get_amount_staked_by_address(owner)
             .ok_or(Error::NotAStaker)?;
-        let new_amount = staked_amount - *amount;
+        let staked_weight = self.weighted_stake(owner);
+        let remaining_amount = staked_amount.safe_sub(*amount)?;
+        // Rounding leaves no weight behind once the whole stake is gone.
+        if remaining_amount.is_zero() {
+            weight = staked_weight;
+        }
+
         self.addresses_staked_dict
-            .set(&key_to_str(owner), new_amount);
-        Ok(())
+            .set(&key_to_str(owner), remaining_amount);
+        self.weighted_stakes_dict
+            .set(&key_to_str(owner), staked_weight.safe_sub(weight)?);
+        Ok(weight)
+    }
+
+    /// Moves `amount` from the position `id` of `from`, or from all its positions oldest first,
+    /// regardless of their lock, to new positions of `to` keeping their tier and unlock time, and
+    /// returns the reward weight moved along with it.
+    pub fn transfer_stake(
+        &self,
+        from: &Key,
+        to: &Key,
+        id: Option<u64>,
+        amount: &U256,
+        lock_tiers: &[LockTier],
+    ) -> Result<U256, Error> {
+        let positions = match id {
+            Some(id) => vec![self.position(from, id).ok_or(Error::UnknownPosition)?],
+            None => self.positions(from),
+        };
+        let mut remaining = *amount;
+        let mut weight = U256::zero();
+        let mut moved = Vec::new();
+        for position in positions {
+            if remaining.is_zero() {
+                break;
+            }
+            let taken = position.amount.min(remaining);
+            remaining = remaining.safe_sub(taken)?;
+            let taken_weight = self.take_from_position(from, position, taken, lock_tiers)?;
+            weight = weight.safe_add(taken_weight)?;
+            moved.push((
+                Position {
+                    amount: taken,
+                    ..position
+                },
+                taken_weight,
+            ));
+        }
+        if !remaining.is_zero() {
+            return Err(Error::NotRequiredStake);
+        }
+
+        // The last position moved takes whatever weight rounding left to the sender.
+        let weight = self.take_from_stake(from, amount, weight)?;
+        let mut remaining_weight = weight;
+        let last = moved.len().saturating_sub(1);
+        for (index, (position, taken_weight)) in moved.into_iter().enumerate() {
+            let taken_weight = if index == last {
+                remaining_weight
+            } else {
+                taken_weight.min(remaining_weight)
+            };
+            remaining_weight = remaining_weight.safe_sub(taken_weight)?;
+            self.add_stake(
+                to,
+                position.amount,
+                position.timestamp,
+                position.lock_tier,
+                position.unlock_time,
+                taken_weight,
+            )?;
+        }
+        Ok(weight)
+    }
+
+    /// Withdraws every position of `owner` regardless of its lock, and returns the amount and the
+    /// reward weight withdrawn.
+    pub fn withdraw_all(&self, owner: &Key) -> (U256, U256) {
+        let amount = self.get_amount_staked_by_address(owner).unwrap_or_default();
+        let weight = self.weighted_stake(owner);
+
+        for position in self.positions(owner) {
+            self.positions_dict.set(
+                &key_and_value_to_str(owner, &position.id),
+                Position {
+                    amount: U256::zero(),
+                    ..position
+                },
+            );
+        }
+        self.set_live_position_ids(owner, Vec::new());
+        self.addresses_staked_dict
+            .set(&key_to_str(owner), U256::zero());
+        self.weighted_stakes_dict
+            .set(&key_to_str(owner), U256::zero());
+        (amount, weight)
+    }
+}
+
+/// CEP-78 tokens minted for positions, indexed by position and by token identifier.
+pub struct PositionNfts {
+    token_ids_dict: Dict,
+    positions_dict: Dict,
+}
+
+impl PositionNfts {
+    pub fn instance() -> PositionNfts {
+        PositionNfts {
+            token_ids_dict: dict(POSITION_TOKEN_IDS_DICT),
+            positions_dict: dict(TOKEN_POSITIONS_DICT),
+        }
+    }
+
+    pub fn init() {
+        init_dict(POSITION_TOKEN_IDS_DICT);
+        init_dict(TOKEN_POSITIONS_DICT);
+    }
+
+    /// Token standing for the position `id` of `staker`.
+    pub fn token_id(&self, staker: &Key, id: u64) -> Option<u64> {
+        self.token_ids_dict.get(&key_and_value_to_str(staker, &id))
+    }
+
+    /// Staker and id of the position `token_id` stands for.
+    pub fn position(&self, token_id: u64) -> Option<(Key, u64)> {
+        self.positions_dict.get(&token_id.to_string())
+    }
+
+    pub fn set_token_id(&self, staker: &Key, id: u64, token_id: u64) {
+        self.token_ids_dict
+            .set(&key_and_value_to_str(staker, &id), token_id);
+        self.positions_dict
+            .set(&token_id.to_string(), (*staker, id));
+    }
+
+    pub fn remove_token_id(&self, staker: &Key, id: u64, token_id: u64) {
+        self.token_ids_dict
+            .remove::<u64>(&key_and_value_to_str(staker, &id));
+        self.positions_dict
+            .remove::<(Key, u64)>(&token_id.to_string());
+    }
+}
+
+/// Operators allowed by stakers to withdraw and claim on their behalf, and the accounts stakers
+/// have their payouts sent to instead of themselves.
+pub struct Operators {
+    operators_dict: Dict,
+    payout_destinations_dict: Dict,
+}
+
+impl Operators {
+    pub fn instance() -> Operators {
+        Operators {
+            operators_dict: dict(OPERATORS_DICT),
+            payout_destinations_dict: dict(PAYOUT_DESTINATIONS_DICT),
+        }
+    }
+
+    pub fn init() {
+        init_dict(OPERATORS_DICT);
+        init_dict(PAYOUT_DESTINATIONS_DICT);
+    }
+
+    pub fn is_operator(&self, staker: &Key, operator: &Key) -> bool {
+        self.operators_dict
+            .get(&keys_to_str(staker, operator))
+            .unwrap_or_default()
+    }
+
+    pub fn set_operator(&self, staker: &Key, operator: &Key, approved: bool) {
+        self.operators_dict
+            .set(&keys_to_str(staker, operator), approved);
+    }
+
+    /// Account the payouts of `staker` are sent to, if it is not the staker itself.
+    pub fn payout_destination(&self, staker: &Key) -> Option<Key> {
+        self.payout_destinations_dict.get(&key_to_str(staker))
+    }
+
+    pub fn set_payout_destination(&self, staker: &Key, destination: Option<Key>) {
+        match destination {
+            Some(destination) => self
+                .payout_destinations_dict
+                .set(&key_to_str(staker), destination),
+            None => self
+                .payout_destinations_dict
+                .remove::<Key>(&key_to_str(staker)),
+        }
+    }
+}
+
+/// Caps set by an admin on the amount single addresses may have staked, overriding
+/// [`StakeLimits::max_stake_per_address`].
+pub struct StakeCaps {
+    stake_caps_dict: Dict,
+}
+
+impl StakeCaps {
+    pub fn instance() -> StakeCaps {
+        StakeCaps {
+            stake_caps_dict: dict(STAKE_CAPS_DICT),
+        }
+    }
+
+    pub fn init() {
+        init_dict(STAKE_CAPS_DICT);
+    }
+
+    pub fn cap(&self, staker: &Key) -> Option<U256> {
+        self.stake_caps_dict.get(&key_to_str(staker))
+    }
+
+    pub fn set_cap(&self, staker: &Key, cap: Option<U256>) {
+        match cap {
+            Some(cap) => self.stake_caps_dict.set(&key_to_str(staker), cap),
+            None => self.stake_caps_dict.remove::<U256>(&key_to_str(staker)),
+        }
+    }
+}
+
+/// Referrers of the stakers that named one, and the rewards credited to every referrer.
+pub struct Referrals {
+    referrers_dict: Dict,
+    referral_rewards_dict: Dict,
+}
+
+impl Referrals {
+    pub fn instance() -> Referrals {
+        Referrals {
+            referrers_dict: dict(REFERRERS_DICT),
+            referral_rewards_dict: dict(REFERRAL_REWARDS_DICT),
+        }
+    }
+
+    pub fn init() {
+        init_dict(REFERRERS_DICT);
+        init_dict(REFERRAL_REWARDS_DICT);
+    }
+
+    pub fn referrer(&self, staker: &Key) -> Option<Key> {
+        self.referrers_dict.get(&key_to_str(staker))
+    }
+
+    pub fn set_referrer(&self, staker: &Key, referrer: Key) {
+        self.referrers_dict.set(&key_to_str(staker), referrer);
+    }
+
+    pub fn referral_rewards(&self, referrer: &Key) -> U256 {
+        self.referral_rewards_dict
+            .get(&key_to_str(referrer))
+            .unwrap_or_default()
+    }
+
+    pub fn set_referral_rewards(&self, referrer: &Key, referral_rewards: U256) {
+        self.referral_rewards_dict
+            .set(&key_to_str(referrer), referral_rewards);
+    }
+}
+
+/// Per-staker reward bookkeeping.
+///
+/// `reward_debt` and `pending_rewards` hold the state of the streaming reward accumulator,
+/// `prepaid_rewards` the pro-rata rewards claimed ahead of withdrawing the principal they were
+/// earned on, `claimed_rewards` the total rewards paid out through `claim_rewards`, and
+/// `reward_remainders` the fraction of a reward token carried over by the fixed-point reward mode,
+/// scaled by [`crate::reward::REWARD_PRECISION`].
+pub struct StakerRewards {
+    reward_debt_dict: Dict,
+    pending_rewards_dict: Dict,
+    prepaid_rewards_dict: Dict,
+    claimed_rewards_dict: Dict,
+    reward_remainders_dict: Dict,
+}
+
+impl StakerRewards {
+    pub fn instance() -> StakerRewards {
+        StakerRewards {
+            reward_debt_dict: dict(REWARD_DEBT_DICT),
+            pending_rewards_dict: dict(PENDING_REWARDS_DICT),
+            prepaid_rewards_dict: dict(PREPAID_REWARDS_DICT),
+            claimed_rewards_dict: dict(CLAIMED_REWARDS_DICT),
+            reward_remainders_dict: dict(REWARD_REMAINDERS_DICT),
+        }
+    }
+
+    pub fn init() {
+        init_dict(REWARD_DEBT_DICT);
+        init_dict(PENDING_REWARDS_DICT);
+        init_dict(PREPAID_REWARDS_DICT);
+        init_dict(CLAIMED_REWARDS_DICT);
+        init_dict(REWARD_REMAINDERS_DICT);
+    }
+
+    pub fn reward_debt(&self, staker: &Key) -> U256 {
+        self.reward_debt_dict
+            .get(&key_to_str(staker))
+            .unwrap_or_default()
+    }
+
+    pub fn set_reward_debt(&self, staker: &Key, reward_debt: U256) {
+        self.reward_debt_dict.set(&key_to_str(staker), reward_debt);
+    }
+
+    pub fn pending_rewards(&self, staker: &Key) -> U256 {
+        self.pending_rewards_dict
+            .get(&key_to_str(staker))
+            .unwrap_or_default()
+    }
+
+    pub fn set_pending_rewards(&self, staker: &Key, pending_rewards: U256) {
+        self.pending_rewards_dict
+            .set(&key_to_str(staker), pending_rewards);
+    }
+
+    pub fn prepaid_rewards(&self, staker: &Key) -> U256 {
+        self.prepaid_rewards_dict
+            .get(&key_to_str(staker))
+            .unwrap_or_default()
+    }
+
+    pub fn set_prepaid_rewards(&self, staker: &Key, prepaid_rewards: U256) {
+        self.prepaid_rewards_dict
+            .set(&key_to_str(staker), prepaid_rewards);
+    }
+
+    pub fn claimed_rewards(&self, staker: &Key) -> U256 {
+        self.claimed_rewards_dict
+            .get(&key_to_str(staker))
+            .unwrap_or_default()
+    }
+
+    pub fn set_claimed_rewards(&self, staker: &Key, claimed_rewards: U256) {
+        self.claimed_rewards_dict
+            .set(&key_to_str(staker), claimed_rewards);
+    }
+
+    pub fn reward_remainder(&self, staker: &Key) -> U256 {
+        self.reward_remainders_dict
+            .get(&key_to_str(staker))
+            .unwrap_or_default()
+    }
+
+    pub fn set_reward_remainder(&self, staker: &Key, reward_remainder: U256) {
+        self.reward_remainders_dict
+            .set(&key_to_str(staker), reward_remainder);
     }
 }
 
 pub fn name() -> String {
-    get_key(NAME).unwrap_or_revert()
+    read(NAME).unwrap_or_revert()
 }
 
 pub fn set_name(name: String) {
-    set_key(NAME, name);
+    write(NAME, name);
 }
 
 pub fn address() -> String {
-    get_key(ADDRESS).unwrap_or_revert()
+    read(ADDRESS).unwrap_or_revert()
 }
 
 pub fn set_address(address: String) {
-    set_key(ADDRESS, address);
+    write(ADDRESS, address);
 }
 
 pub fn staking_starts() -> u64 {
-    get_key(STAKING_STARTS).unwrap_or_revert()
+    read(STAKING_STARTS).unwrap_or_revert()
 }
 
 pub fn set_staking_starts(staking_starts: u64) {
-    set_key(STAKING_STARTS, staking_starts);
+    write(STAKING_STARTS, staking_starts);
 }
 
 pub fn staking_ends() -> u64 {
-    get_key(STAKING_ENDS).unwrap_or_revert()
+    read(STAKING_ENDS).unwrap_or_revert()
 }
 
 pub fn set_staking_ends(staking_ends: u64) {
-    set_key(STAKING_ENDS, staking_ends);
+    write(STAKING_ENDS, staking_ends);
 }
 
 pub fn withdraw_starts() -> u64 {
-    get_key(WITHDRAW_STARTS).unwrap_or_default()
+    read(WITHDRAW_STARTS).unwrap_or_default()
 }
 
 pub fn set_withdraw_starts(withdraw_starts: u64) {
-    set_key(WITHDRAW_STARTS, withdraw_starts);
+    write(WITHDRAW_STARTS, withdraw_starts);
 }
 
 pub fn withdraw_ends() -> u64 {
-    get_key(WITHDRAW_ENDS).unwrap_or_default()
+    read(WITHDRAW_ENDS).unwrap_or_default()
 }
 
 pub fn set_withdraw_ends(withdraw_ends: u64) {
-    set_key(WITHDRAW_STARTS, withdraw_ends);
+    write(WITHDRAW_ENDS, withdraw_ends);
 }
 
 pub fn staking_total() -> U256 {
-    get_key(STAKING_TOTAL).unwrap_or_default()
+    read(STAKING_TOTAL).unwrap_or_default()
 }
 
 pub fn set_staking_total(staking_total: U256) {
-    set_key(STAKING_TOTAL, staking_total);
+    write(STAKING_TOTAL, staking_total);
 }
 
 pub fn stake_balance() -> u64 {
-    get_key(STAKED_BALANCE).unwrap_or_default()
+    read(STAKED_BALANCE).unwrap_or_default()
 }
 
 pub fn set_stake_balance(stake_balance: u64) {
-    set_key(STAKED_BALANCE, stake_balance);
+    write(STAKED_BALANCE, stake_balance);
 }
 
 pub fn total_reward() -> U256 {
-    get_key(TOTAL_REWARD).unwrap_or_default()
+    read(TOTAL_REWARD).unwrap_or_default()
 }
 
 pub fn set_total_reward(total_reward: U256) {
-    set_key(TOTAL_REWARD, total_reward);
+    write(TOTAL_REWARD, total_reward);
 }
 
 pub fn early_withdraw_reward() -> U256 {
-    get_key(EARLY_WITHDRAW_REWARD).unwrap_or_default()
+    read(EARLY_WITHDRAW_REWARD).unwrap_or_default()
 }
 
 pub fn set_early_withdraw_reward(early_withdraw_reward: U256) {
-    set_key(EARLY_WITHDRAW_REWARD, early_withdraw_reward);
+    write(EARLY_WITHDRAW_REWARD, early_withdraw_reward);
 }
 
 pub fn staked_total() -> U256 {
-    get_key(STAKED_TOTAL).unwrap_or_default()
+    read(STAKED_TOTAL).unwrap_or_default()
 }
 
 pub fn set_staked_total(staked_total: U256) {
-    set_key(STAKED_TOTAL, staked_total);
+    write(STAKED_TOTAL, staked_total);
+}
+
+pub fn weighted_total() -> U256 {
+    read(WEIGHTED_TOTAL).unwrap_or_default()
+}
+
+pub fn set_weighted_total(weighted_total: U256) {
+    write(WEIGHTED_TOTAL, weighted_total);
 }
 
 pub fn reward_balance() -> U256 {
-    get_key(REWARD_BALANCE).unwrap_or_default()
+    read(REWARD_BALANCE).unwrap_or_default()
 }
 
 pub fn set_reward_balance(reward_balance: U256) {
-    set_key(REWARD_BALANCE, reward_balance);
+    write(REWARD_BALANCE, reward_balance);
 }
 
 pub fn staked_balance() -> U256 {
-    get_key(STAKED_BALANCE).unwrap_or_default()
+    read(STAKED_BALANCE).unwrap_or_default()
 }
 
 pub fn set_staked_balance(staked_balance: U256) {
-    set_key(STAKED_BALANCE, staked_balance);
+    write(STAKED_BALANCE, staked_balance);
+}
+
+/// Reward tokens paid out or swept so far.
+pub fn distributed_rewards() -> U256 {
+    read(DISTRIBUTED_REWARDS).unwrap_or_default()
+}
+
+pub fn set_distributed_rewards(distributed_rewards: U256) {
+    write(DISTRIBUTED_REWARDS, distributed_rewards);
+}
+
+/// Staked tokens not withdrawn yet. Unlike `staked_balance`, which the pro-rata reward modes keep
+/// as it stood at `withdraw_ends`, it drops with every withdrawal.
+pub fn outstanding_stake() -> U256 {
+    read(OUTSTANDING_STAKE).unwrap_or_default()
+}
+
+pub fn set_outstanding_stake(outstanding_stake: U256) {
+    write(OUTSTANDING_STAKE, outstanding_stake);
+}
+
+/// Staking tokens held by the vault in [`RewardMode::Vault`], compounded rewards included.
+pub fn vault_assets() -> U256 {
+    read(VAULT_ASSETS).unwrap_or_default()
+}
+
+pub fn set_vault_assets(vault_assets: U256) {
+    write(VAULT_ASSETS, vault_assets);
+}
+
+pub fn reward_mode() -> RewardMode {
+    let reward_mode: u8 = read(REWARD_MODE).unwrap_or_default();
+    RewardMode::try_from(reward_mode).unwrap_or_revert()
+}
+
+pub fn set_reward_mode(reward_mode: RewardMode) {
+    write(REWARD_MODE, reward_mode as u8);
+}
+
+pub fn reward_rate() -> U256 {
+    read(REWARD_RATE).unwrap_or_default()
+}
+
+pub fn set_reward_rate(reward_rate: U256) {
+    write(REWARD_RATE, reward_rate);
+}
+
+pub fn reward_per_token_stored() -> U256 {
+    read(REWARD_PER_TOKEN_STORED).unwrap_or_default()
+}
+
+pub fn set_reward_per_token_stored(reward_per_token_stored: U256) {
+    write(REWARD_PER_TOKEN_STORED, reward_per_token_stored);
+}
+
+pub fn last_update_time() -> u64 {
+    read(LAST_UPDATE_TIME).unwrap_or_default()
+}
+
+pub fn set_last_update_time(last_update_time: u64) {
+    write(LAST_UPDATE_TIME, last_update_time);
+}
+
+pub fn lock_tiers() -> Vec<LockTier> {
+    let lock_tiers: Vec<(u64, u64)> = read(LOCK_TIERS).unwrap_or_default();
+    lock_tiers.into_iter().map(LockTier::from).collect()
+}
+
+pub fn set_lock_tiers(lock_tiers: Vec<LockTier>) {
+    let lock_tiers: Vec<(u64, u64)> = lock_tiers.into_iter().map(Into::into).collect();
+    write(LOCK_TIERS, lock_tiers);
+}
+
+pub fn weighted_balance() -> U256 {
+    read(WEIGHTED_BALANCE).unwrap_or_default()
+}
+
+pub fn set_weighted_balance(weighted_balance: U256) {
+    write(WEIGHTED_BALANCE, weighted_balance);
+}
+
+/// Early withdrawal penalty of the pool, none unless one was set at install.
+pub fn penalty() -> Penalty {
+    let mode: u8 = read(PENALTY_MODE).unwrap_or_default();
+    Penalty {
+        rate: read(EARLY_WITHDRAW_PENALTY).unwrap_or_default(),
+        mode: PenaltyMode::try_from(mode).unwrap_or_revert(),
+        treasury: read(TREASURY).unwrap_or_default(),
+    }
+}
+
+pub fn set_penalty(penalty: Penalty) {
+    write(EARLY_WITHDRAW_PENALTY, penalty.rate);
+    write(PENALTY_MODE, penalty.mode as u8);
+    write(TREASURY, penalty.treasury);
+}
+
+pub fn stake_limits() -> StakeLimits {
+    StakeLimits {
+        min_stake: read(MIN_STAKE).unwrap_or_default(),
+        max_stake_per_address: read(MAX_STAKE_PER_ADDRESS).unwrap_or_default(),
+    }
+}
+
+pub fn set_stake_limits(stake_limits: StakeLimits) {
+    write(MIN_STAKE, stake_limits.min_stake);
+    write(MAX_STAKE_PER_ADDRESS, stake_limits.max_stake_per_address);
+}
+
+/// Share of the rewards of referred stakers credited to their referrer, in basis points.
+pub fn referral_rate() -> u64 {
+    read(REFERRAL_RATE).unwrap_or_default()
+}
+
+pub fn set_referral_rate(referral_rate: u64) {
+    write(REFERRAL_RATE, referral_rate);
+}
+
+/// Merkle root of the allowlist of the pool, if it has one.
+pub fn allowlist_root() -> Option<Hash> {
+    read(ALLOWLIST_ROOT).unwrap_or_default()
+}
+
+pub fn set_allowlist_root(allowlist_root: Option<Hash>) {
+    write(ALLOWLIST_ROOT, allowlist_root);
+}
+
+/// Package hash of the CEP-78 collection positions are minted in, if the pool mints any.
+pub fn position_nft() -> Option<Key> {
+    read(POSITION_NFT).unwrap_or_default()
+}
+
+pub fn set_position_nft(position_nft: Option<Key>) {
+    write(POSITION_NFT, position_nft);
+}
+
+pub fn paused() -> bool {
+    read(PAUSED).unwrap_or_default()
+}
+
+pub fn set_paused(paused: bool) {
+    write(PAUSED, paused);
+}
+
+pub fn schema_version() -> u32 {
+    read(SCHEMA_VERSION).unwrap_or_default()
+}
+
+pub fn set_schema_version(schema_version: u32) {
+    write(SCHEMA_VERSION, schema_version);
+}
+
+pub fn erc20_contract_package_hash() -> Key {
+    read(ERC20_CONTRACT_PACKAGE_HASH).unwrap_or_revert_with(Error::MissingContractPackageHash)
+}
+
+pub fn set_erc20_contract_package_hash(erc20_contract_package_hash: Key) {
+    write(ERC20_CONTRACT_PACKAGE_HASH, erc20_contract_package_hash);
+}
+
+pub fn reward_token_package_hash() -> Key {
+    read(REWARD_TOKEN_PACKAGE_HASH).unwrap_or_revert_with(Error::MissingContractPackageHash)
+}
+
+pub fn set_reward_token_package_hash(reward_token_package_hash: Key) {
+    write(REWARD_TOKEN_PACKAGE_HASH, reward_token_package_hash);
 }
 
 pub fn contract_package_hash() -> ContractPackageHash {
@@ -187,73 +1010,53 @@ pub fn contract_package_hash() -> ContractPackageHash {
     package_hash.unwrap_or_revert()
 }
 
+/// Sets up the storage of the Casper Event Standard: the `__events` dictionary, its length and the
+/// published event schemas.
+pub fn init_events() {
+    storage::new_dictionary(EVENTS_DICT).unwrap_or_revert();
+    set_key(EVENTS_LENGTH, 0u32);
+    publish_event_schemas();
+    set_key(EVENTS_CES_VERSION, CES_VERSION.to_string());
+}
+
+/// Creates the event log of the selected factory pool: the `__events_{pool_id}` dictionary, with
+/// its length kept in the pool namespace. The schemas are the ones published by the factory.
+pub fn init_pool_events() {
+    init_dict(EVENTS_DICT);
+    write(EVENTS_LENGTH, 0u32);
+}
+
+/// Publishes the schemas of every event this build emits, replacing the ones stored by an earlier
+/// version.
+pub fn publish_event_schemas() {
+    set_key(EVENTS_SCHEMA, StakingContractEvent::schemas());
+}
+
+/// Appends `event` to the `__events` dictionary, or to the one of the selected factory pool, under
+/// the next event index.
 pub fn emit(event: &StakingContractEvent) {
-    let mut events = Vec::new();
-    let package = contract_package_hash();
-    match event {
-        StakingContractEvent::Stake {
-            token_address,
-            staker_address,
-            requested_amount,
-            staked_amount,
-        } => {
-            let mut param = BTreeMap::new();
-            param.insert(CONTRACT_PACKAGE_HASH, package.to_string());
-            param.insert("event_type", "stake".to_string());
-            param.insert("token_address", token_address.to_string());
-            param.insert(
-                "staker_address",
-                TryInto::<String>::try_into(*staker_address).unwrap(),
-            );
-            param.insert("requested_amount", requested_amount.to_string());
-            param.insert("staked_amount", staked_amount.to_string());
-            events.push(param);
-        }
-        StakingContractEvent::PaidOut {
-            token_address,
-            staker_address,
-            amount,
-            reward,
-        } => {
-            let mut param = BTreeMap::new();
-            param.insert(CONTRACT_PACKAGE_HASH, package.to_string());
-            param.insert("token_address", token_address.to_string());
-            param.insert(
-                "staker_address",
-                TryInto::<String>::try_into(*staker_address).unwrap(),
-            );
-            param.insert("amount", amount.to_string());
-            param.insert("reward", reward.to_string());
-            events.push(param);
-        }
-        StakingContractEvent::AddReward {
-            reward_amount,
-            withdrawable_amount,
-        } => {
-            let mut param = BTreeMap::new();
-            param.insert(CONTRACT_PACKAGE_HASH, package.to_string());
-            param.insert("event_type", "add_reward".to_string());
-            param.insert("reward_amount", reward_amount.to_string());
-            param.insert("withdrawable_amount", withdrawable_amount.to_string());
-            events.push(param);
-        }
-        StakingContractEvent::Refunded {
-            token_address,
-            staker_address,
-            amount,
-        } => {
-            let mut param = BTreeMap::new();
-            param.insert(CONTRACT_PACKAGE_HASH, package.to_string());
-            param.insert("token_address", token_address.to_string());
-            param.insert(
-                "staker_address",
-                TryInto::<String>::try_into(*staker_address).unwrap(),
-            );
-            param.insert("amount", amount.to_string());
-            events.push(param);
-        }
+    let events_length: u32 = read(EVENTS_LENGTH).unwrap_or_revert();
+    let events_uref = runtime::get_key(&namespaced(EVENTS_DICT))
+        .and_then(|key| key.into_uref())
+        .unwrap_or_revert();
+    let event = CLValue::from_components(CLType::Any, event.to_bytes().unwrap_or_revert());
+    dictionary_put_cl_value(events_uref, &events_length.to_string(), event);
+    write(EVENTS_LENGTH, events_length.safe_add(1).unwrap_or_revert());
+}
+
+/// Same as [`storage::dictionary_put`], for values that are already wrapped in a [`CLValue`].
+fn dictionary_put_cl_value(dictionary_seed_uref: URef, dictionary_item_key: &str, value: CLValue) {
+    let uref_bytes = dictionary_seed_uref.to_bytes().unwrap_or_revert();
+    let value_bytes = value.to_bytes().unwrap_or_revert();
+    let ret = unsafe {
+        ext_ffi::casper_dictionary_put(
+            uref_bytes.as_ptr(),
+            uref_bytes.len(),
+            dictionary_item_key.as_ptr(),
+            dictionary_item_key.len(),
+            value_bytes.as_ptr(),
+            value_bytes.len(),
+        )
     };
-    for param in events {
-        let _: URef = storage::new_uref(param);
-    }
+    api_error::result_from(ret).unwrap_or_revert();
 }
@@ -1,11 +1,18 @@
 //! Implementation details.
+use alloc::vec;
 use core::convert::TryInto;
 
 use casper_contract::{
     contract_api::{runtime, storage},
+    ext_ffi,
     unwrap_or_revert::UnwrapOrRevert,
 };
-use casper_types::{bytesrepr::FromBytes, system::CallStackElement, ApiError, CLTyped, URef};
+use casper_types::{
+    api_error,
+    bytesrepr::{self, FromBytes},
+    system::CallStackElement,
+    ApiError, CLTyped, URef,
+};
 
 use crate::address::Address;
 use crate::error::Error;
@@ -79,3 +86,33 @@ pub(crate) fn get_caller_address() -> Result<Address, Error> {
     let address = call_stack_element_to_address(top_of_the_stack);
     Ok(address)
 }
+
+/// Same as [`runtime::get_named_arg`], for arguments callers may leave out, which read as `None`.
+pub fn get_optional_named_arg<T: FromBytes>(name: &str) -> Option<T> {
+    let mut arg_size: usize = 0;
+    let ret = unsafe {
+        ext_ffi::casper_get_named_arg_size(
+            name.as_bytes().as_ptr(),
+            name.len(),
+            &mut arg_size as *mut usize,
+        )
+    };
+    match api_error::result_from(ret) {
+        Ok(()) => {}
+        Err(ApiError::MissingArgument) => return None,
+        Err(error) => runtime::revert(error),
+    }
+    let mut arg_bytes = vec![0u8; arg_size];
+    if arg_size > 0 {
+        let ret = unsafe {
+            ext_ffi::casper_get_named_arg(
+                name.as_bytes().as_ptr(),
+                name.len(),
+                arg_bytes.as_mut_ptr(),
+                arg_size,
+            )
+        };
+        api_error::result_from(ret).unwrap_or_revert();
+    }
+    Some(bytesrepr::deserialize(arg_bytes).unwrap_or_revert_with(ApiError::InvalidArgument))
+}
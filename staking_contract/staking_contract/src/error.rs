@@ -20,6 +20,40 @@ pub enum Error {
     NotAStaker = 15,
     ImmediateCallerAddressFail = 16,
     NotStakingContractPackageHash = 17,
+    UnknownPool = 18,
+    InvalidRewardMode = 19,
+    InvalidRole = 20,
+    Paused = 21,
+    NotPaused = 22,
+    UnsupportedSchemaVersion = 23,
+    InvalidLockTier = 24,
+    StakeLocked = 25,
+    UnknownPosition = 26,
+    InvalidPenalty = 27,
+    Overflow = 28,
+    Underflow = 29,
+    DivisionByZero = 30,
+    PoolNotEmpty = 31,
+    NoReceiptToken = 32,
+    PositionNftRequired = 33,
+    InvalidPositionNft = 34,
+    InvalidStakingEnds = 35,
+    InvalidWithdrawStarts = 36,
+    InvalidWithdrawEnds = 37,
+    InvalidStakingTotal = 38,
+    WindowStarted = 39,
+    ScheduleShortened = 40,
+    BelowMinStake = 41,
+    StakeCapExceeded = 42,
+    NotAllowlisted = 43,
+    AllocationExceeded = 44,
+    InvalidReferralRate = 45,
+    InvalidReferrer = 46,
+    ReferralsUnsupported = 47,
+    InsufficientReceiptBalance = 48,
+    InsufficientReceiptAllowance = 49,
+    TooManyPositions = 50,
+    InvalidStakeTerms = 51,
 }
 
 impl From<Error> for ApiError {
@@ -1,6 +1,20 @@
-use crate::address::Address;
-use alloc::string::String;
-use casper_types::U256;
+//! Events emitted by the staking contract.
+//!
+//! Events follow the [Casper Event Standard](https://github.com/make-software/casper-event-standard):
+//! every event is serialized as its `event_<Name>` prefix followed by its fields, in the order
+//! published by [`StakingContractEvent::schemas`].
+use crate::{address::Address, allowlist::Hash, penalty::PenaltyMode, role::Role};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use casper_types::{
+    bytesrepr::{self, ToBytes},
+    CLType, CLTyped, Key, U256,
+};
 
 pub enum StakingContractEvent {
     Stake {
@@ -8,6 +22,11 @@ pub enum StakingContractEvent {
         staker_address: Address,
         requested_amount: U256,
         staked_amount: U256,
+        position_id: u64,
+        lock_tier: u8,
+        unlock_time: u64,
+        /// Account credited with the stake, which differs from `staker_address` for `stake_for`.
+        beneficiary: Address,
     },
     PaidOut {
         token_address: String,
@@ -19,9 +38,481 @@ pub enum StakingContractEvent {
         reward_amount: U256,
         withdrawable_amount: U256,
     },
+    RewardClaimed {
+        token_address: String,
+        staker_address: Address,
+        reward: U256,
+    },
+    RoleGranted {
+        role: Role,
+        account: Key,
+        sender: Key,
+    },
+    RoleRevoked {
+        role: Role,
+        account: Key,
+        sender: Key,
+    },
+    Paused {
+        account: Key,
+    },
+    Unpaused {
+        account: Key,
+    },
+    EmergencyWithdraw {
+        token_address: String,
+        staker_address: Address,
+        amount: U256,
+    },
     Refunded {
         token_address: String,
         staker_address: Address,
         amount: U256,
     },
+    EarlyWithdrawPenalty {
+        token_address: String,
+        staker_address: Address,
+        amount: U256,
+        penalty_mode: PenaltyMode,
+    },
+    DustSwept {
+        token_address: String,
+        recipient: Address,
+        amount: U256,
+    },
+    Compounded {
+        token_address: String,
+        reward: U256,
+    },
+    StakeTransferred {
+        token_address: String,
+        sender: Address,
+        recipient: Address,
+        amount: U256,
+    },
+    OperatorApproved {
+        staker_address: Address,
+        operator: Key,
+    },
+    OperatorRevoked {
+        staker_address: Address,
+        operator: Key,
+    },
+    ScheduleChanged {
+        staking_starts: u64,
+        staking_ends: u64,
+        withdraw_starts: u64,
+        withdraw_ends: u64,
+    },
+    StakeLimitsChanged {
+        min_stake: U256,
+        max_stake_per_address: Option<U256>,
+    },
+    StakeCapChanged {
+        staker_address: Key,
+        cap: Option<U256>,
+    },
+    AllowlistRootChanged {
+        root: Option<Hash>,
+    },
+    ReferralCredited {
+        token_address: String,
+        referrer: Key,
+        staker_address: Address,
+        reward: U256,
+    },
+    ReferralRateChanged {
+        referral_rate: u64,
+    },
+}
+
+impl StakingContractEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StakingContractEvent::Stake { .. } => "Stake",
+            StakingContractEvent::PaidOut { .. } => "PaidOut",
+            StakingContractEvent::AddReward { .. } => "AddReward",
+            StakingContractEvent::RewardClaimed { .. } => "RewardClaimed",
+            StakingContractEvent::RoleGranted { .. } => "RoleGranted",
+            StakingContractEvent::RoleRevoked { .. } => "RoleRevoked",
+            StakingContractEvent::Paused { .. } => "Paused",
+            StakingContractEvent::Unpaused { .. } => "Unpaused",
+            StakingContractEvent::EmergencyWithdraw { .. } => "EmergencyWithdraw",
+            StakingContractEvent::Refunded { .. } => "Refunded",
+            StakingContractEvent::EarlyWithdrawPenalty { .. } => "EarlyWithdrawPenalty",
+            StakingContractEvent::DustSwept { .. } => "DustSwept",
+            StakingContractEvent::Compounded { .. } => "Compounded",
+            StakingContractEvent::StakeTransferred { .. } => "StakeTransferred",
+            StakingContractEvent::OperatorApproved { .. } => "OperatorApproved",
+            StakingContractEvent::OperatorRevoked { .. } => "OperatorRevoked",
+            StakingContractEvent::ScheduleChanged { .. } => "ScheduleChanged",
+            StakingContractEvent::StakeLimitsChanged { .. } => "StakeLimitsChanged",
+            StakingContractEvent::StakeCapChanged { .. } => "StakeCapChanged",
+            StakingContractEvent::AllowlistRootChanged { .. } => "AllowlistRootChanged",
+            StakingContractEvent::ReferralCredited { .. } => "ReferralCredited",
+            StakingContractEvent::ReferralRateChanged { .. } => "ReferralRateChanged",
+        }
+    }
+
+    /// Fields of every event, with their types, in serialization order.
+    pub fn schemas() -> Schemas {
+        let staker_amount = || {
+            vec![
+                ("token_address", String::cl_type()),
+                ("staker_address", Address::cl_type()),
+                ("amount", U256::cl_type()),
+            ]
+        };
+        let role_change = || {
+            vec![
+                ("role", u8::cl_type()),
+                ("account", Key::cl_type()),
+                ("sender", Key::cl_type()),
+            ]
+        };
+        let operator_change = || {
+            vec![
+                ("staker_address", Address::cl_type()),
+                ("operator", Key::cl_type()),
+            ]
+        };
+        Schemas::new(vec![
+            (
+                "Stake",
+                vec![
+                    ("token_address", String::cl_type()),
+                    ("staker_address", Address::cl_type()),
+                    ("requested_amount", U256::cl_type()),
+                    ("staked_amount", U256::cl_type()),
+                    ("position_id", u64::cl_type()),
+                    ("lock_tier", u8::cl_type()),
+                    ("unlock_time", u64::cl_type()),
+                    ("beneficiary", Address::cl_type()),
+                ],
+            ),
+            (
+                "PaidOut",
+                vec![
+                    ("token_address", String::cl_type()),
+                    ("staker_address", Address::cl_type()),
+                    ("amount", U256::cl_type()),
+                    ("reward", U256::cl_type()),
+                ],
+            ),
+            (
+                "AddReward",
+                vec![
+                    ("reward_amount", U256::cl_type()),
+                    ("withdrawable_amount", U256::cl_type()),
+                ],
+            ),
+            (
+                "RewardClaimed",
+                vec![
+                    ("token_address", String::cl_type()),
+                    ("staker_address", Address::cl_type()),
+                    ("reward", U256::cl_type()),
+                ],
+            ),
+            ("RoleGranted", role_change()),
+            ("RoleRevoked", role_change()),
+            ("Paused", vec![("account", Key::cl_type())]),
+            ("Unpaused", vec![("account", Key::cl_type())]),
+            ("EmergencyWithdraw", staker_amount()),
+            ("Refunded", staker_amount()),
+            (
+                "EarlyWithdrawPenalty",
+                vec![
+                    ("token_address", String::cl_type()),
+                    ("staker_address", Address::cl_type()),
+                    ("amount", U256::cl_type()),
+                    ("penalty_mode", u8::cl_type()),
+                ],
+            ),
+            (
+                "DustSwept",
+                vec![
+                    ("token_address", String::cl_type()),
+                    ("recipient", Address::cl_type()),
+                    ("amount", U256::cl_type()),
+                ],
+            ),
+            (
+                "Compounded",
+                vec![
+                    ("token_address", String::cl_type()),
+                    ("reward", U256::cl_type()),
+                ],
+            ),
+            (
+                "StakeTransferred",
+                vec![
+                    ("token_address", String::cl_type()),
+                    ("sender", Address::cl_type()),
+                    ("recipient", Address::cl_type()),
+                    ("amount", U256::cl_type()),
+                ],
+            ),
+            ("OperatorApproved", operator_change()),
+            ("OperatorRevoked", operator_change()),
+            (
+                "ScheduleChanged",
+                vec![
+                    ("staking_starts", u64::cl_type()),
+                    ("staking_ends", u64::cl_type()),
+                    ("withdraw_starts", u64::cl_type()),
+                    ("withdraw_ends", u64::cl_type()),
+                ],
+            ),
+            (
+                "StakeLimitsChanged",
+                vec![
+                    ("min_stake", U256::cl_type()),
+                    ("max_stake_per_address", Option::<U256>::cl_type()),
+                ],
+            ),
+            (
+                "StakeCapChanged",
+                vec![
+                    ("staker_address", Key::cl_type()),
+                    ("cap", Option::<U256>::cl_type()),
+                ],
+            ),
+            (
+                "AllowlistRootChanged",
+                vec![("root", Option::<Hash>::cl_type())],
+            ),
+            (
+                "ReferralCredited",
+                vec![
+                    ("token_address", String::cl_type()),
+                    ("referrer", Key::cl_type()),
+                    ("staker_address", Address::cl_type()),
+                    ("reward", U256::cl_type()),
+                ],
+            ),
+            (
+                "ReferralRateChanged",
+                vec![("referral_rate", u64::cl_type())],
+            ),
+        ])
+    }
+}
+
+impl ToBytes for StakingContractEvent {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut bytes = format!("event_{}", self.name()).to_bytes()?;
+        match self {
+            StakingContractEvent::Stake {
+                token_address,
+                staker_address,
+                requested_amount,
+                staked_amount,
+                position_id,
+                lock_tier,
+                unlock_time,
+                beneficiary,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut staker_address.to_bytes()?);
+                bytes.append(&mut requested_amount.to_bytes()?);
+                bytes.append(&mut staked_amount.to_bytes()?);
+                bytes.append(&mut position_id.to_bytes()?);
+                bytes.append(&mut lock_tier.to_bytes()?);
+                bytes.append(&mut unlock_time.to_bytes()?);
+                bytes.append(&mut beneficiary.to_bytes()?);
+            }
+            StakingContractEvent::PaidOut {
+                token_address,
+                staker_address,
+                amount,
+                reward,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut staker_address.to_bytes()?);
+                bytes.append(&mut amount.to_bytes()?);
+                bytes.append(&mut reward.to_bytes()?);
+            }
+            StakingContractEvent::AddReward {
+                reward_amount,
+                withdrawable_amount,
+            } => {
+                bytes.append(&mut reward_amount.to_bytes()?);
+                bytes.append(&mut withdrawable_amount.to_bytes()?);
+            }
+            StakingContractEvent::RewardClaimed {
+                token_address,
+                staker_address,
+                reward,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut staker_address.to_bytes()?);
+                bytes.append(&mut reward.to_bytes()?);
+            }
+            StakingContractEvent::RoleGranted {
+                role,
+                account,
+                sender,
+            }
+            | StakingContractEvent::RoleRevoked {
+                role,
+                account,
+                sender,
+            } => {
+                bytes.append(&mut (*role as u8).to_bytes()?);
+                bytes.append(&mut account.to_bytes()?);
+                bytes.append(&mut sender.to_bytes()?);
+            }
+            StakingContractEvent::Paused { account }
+            | StakingContractEvent::Unpaused { account } => {
+                bytes.append(&mut account.to_bytes()?);
+            }
+            StakingContractEvent::EmergencyWithdraw {
+                token_address,
+                staker_address,
+                amount,
+            }
+            | StakingContractEvent::Refunded {
+                token_address,
+                staker_address,
+                amount,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut staker_address.to_bytes()?);
+                bytes.append(&mut amount.to_bytes()?);
+            }
+            StakingContractEvent::EarlyWithdrawPenalty {
+                token_address,
+                staker_address,
+                amount,
+                penalty_mode,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut staker_address.to_bytes()?);
+                bytes.append(&mut amount.to_bytes()?);
+                bytes.append(&mut (*penalty_mode as u8).to_bytes()?);
+            }
+            StakingContractEvent::DustSwept {
+                token_address,
+                recipient,
+                amount,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut recipient.to_bytes()?);
+                bytes.append(&mut amount.to_bytes()?);
+            }
+            StakingContractEvent::Compounded {
+                token_address,
+                reward,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut reward.to_bytes()?);
+            }
+            StakingContractEvent::StakeTransferred {
+                token_address,
+                sender,
+                recipient,
+                amount,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut sender.to_bytes()?);
+                bytes.append(&mut recipient.to_bytes()?);
+                bytes.append(&mut amount.to_bytes()?);
+            }
+            StakingContractEvent::OperatorApproved {
+                staker_address,
+                operator,
+            }
+            | StakingContractEvent::OperatorRevoked {
+                staker_address,
+                operator,
+            } => {
+                bytes.append(&mut staker_address.to_bytes()?);
+                bytes.append(&mut operator.to_bytes()?);
+            }
+            StakingContractEvent::ScheduleChanged {
+                staking_starts,
+                staking_ends,
+                withdraw_starts,
+                withdraw_ends,
+            } => {
+                bytes.append(&mut staking_starts.to_bytes()?);
+                bytes.append(&mut staking_ends.to_bytes()?);
+                bytes.append(&mut withdraw_starts.to_bytes()?);
+                bytes.append(&mut withdraw_ends.to_bytes()?);
+            }
+            StakingContractEvent::StakeLimitsChanged {
+                min_stake,
+                max_stake_per_address,
+            } => {
+                bytes.append(&mut min_stake.to_bytes()?);
+                bytes.append(&mut max_stake_per_address.to_bytes()?);
+            }
+            StakingContractEvent::StakeCapChanged {
+                staker_address,
+                cap,
+            } => {
+                bytes.append(&mut staker_address.to_bytes()?);
+                bytes.append(&mut cap.to_bytes()?);
+            }
+            StakingContractEvent::AllowlistRootChanged { root } => {
+                bytes.append(&mut root.to_bytes()?);
+            }
+            StakingContractEvent::ReferralCredited {
+                token_address,
+                referrer,
+                staker_address,
+                reward,
+            } => {
+                bytes.append(&mut token_address.to_bytes()?);
+                bytes.append(&mut referrer.to_bytes()?);
+                bytes.append(&mut staker_address.to_bytes()?);
+                bytes.append(&mut reward.to_bytes()?);
+            }
+            StakingContractEvent::ReferralRateChanged { referral_rate } => {
+                bytes.append(&mut referral_rate.to_bytes()?);
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.to_bytes().map(|bytes| bytes.len()).unwrap_or_default()
+    }
+}
+
+/// Event schemas published under the `__events_schema` named key, keyed by event name.
+pub struct Schemas(BTreeMap<String, Vec<(String, CLType)>>);
+
+impl Schemas {
+    fn new(schemas: Vec<(&str, Vec<(&str, CLType)>)>) -> Self {
+        Schemas(
+            schemas
+                .into_iter()
+                .map(|(event, fields)| {
+                    let fields = fields
+                        .into_iter()
+                        .map(|(field, cl_type)| (field.to_string(), cl_type))
+                        .collect();
+                    (event.to_string(), fields)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl CLTyped for Schemas {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+impl ToBytes for Schemas {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        self.0.to_bytes()
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.0.serialized_length()
+    }
 }
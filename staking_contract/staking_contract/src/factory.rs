@@ -0,0 +1,84 @@
+//! Factory hosting several [`CEP20STK`] pools under a single contract package.
+//!
+//! Every pool gets its own storage namespace (see [`data::set_namespace`]), so the regular staking
+//! logic runs unchanged once the pool has been selected for the current call. Roles are shared by
+//! all the pools of the factory.
+use crate::{
+    allowlist::Hash, data, error::Error, lock::LockTier, penalty::Penalty, reward::RewardMode,
+    role::Role, staking_contract::CEP20STK,
+};
+use alloc::{string::String, vec::Vec};
+use casper_types::{Key, U256};
+use contract_utils::{get_key, set_key, ContractStorage};
+
+pub const POOL_COUNT: &str = "pool_count";
+
+pub trait CEP20STKFactory<Storage: ContractStorage>: CEP20STK<Storage> {
+    fn init(&mut self) {
+        let installer = self.get_caller();
+        data::init_events();
+        self.init_roles(installer);
+        set_key(POOL_COUNT, 0u64);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_pool(
+        &mut self,
+        name: String,
+        address: String,
+        staking_starts: u64,
+        staking_ends: u64,
+        withdraw_starts: u64,
+        withdraw_ends: u64,
+        staking_total: U256,
+        erc20_contract_package_hash: Key,
+        reward_token_package_hash: Key,
+        reward_mode: RewardMode,
+        lock_tiers: Vec<LockTier>,
+        penalty: Penalty,
+        position_nft: Option<Key>,
+        allowlist_root: Option<Hash>,
+    ) -> Result<u64, Error> {
+        self.only_role(Role::Owner)?;
+
+        let pool_id = self.pool_count();
+        data::init_namespace(pool_id);
+        data::set_namespace(Some(pool_id));
+        data::init_pool_events();
+        CEP20STK::init(
+            self,
+            name,
+            address,
+            staking_starts,
+            staking_ends,
+            withdraw_starts,
+            withdraw_ends,
+            staking_total,
+            erc20_contract_package_hash,
+            reward_token_package_hash,
+            reward_mode,
+            lock_tiers,
+            penalty,
+            position_nft,
+            allowlist_root,
+        );
+        set_key(POOL_COUNT, pool_id + 1);
+        Ok(pool_id)
+    }
+
+    fn select_pool(&self, pool_id: u64) -> Result<(), Error> {
+        if pool_id >= self.pool_count() {
+            return Err(Error::UnknownPool);
+        }
+        data::set_namespace(Some(pool_id));
+        Ok(())
+    }
+
+    fn pool_count(&self) -> u64 {
+        get_key(POOL_COUNT).unwrap_or_default()
+    }
+
+    fn list_pools(&self) -> Vec<u64> {
+        (0..self.pool_count()).collect()
+    }
+}
@@ -2,9 +2,23 @@
 extern crate alloc;
 
 pub mod address;
+pub mod allowlist;
 pub mod data;
 pub mod detail;
 pub mod error;
 pub mod event;
+pub mod factory;
+pub mod limit;
+pub mod lock;
 pub mod modifiers;
+pub mod penalty;
+pub mod permit;
+pub mod phase;
+pub mod position_nft;
+pub mod receipt;
+pub mod referral;
+pub mod reward;
+pub mod role;
+pub mod safe_math;
+pub mod schedule;
 pub mod staking_contract;
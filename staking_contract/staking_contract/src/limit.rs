@@ -0,0 +1,32 @@
+//! Limits on the amounts a staker can stake.
+use crate::{error::Error, safe_math::SafeMath};
+use casper_types::U256;
+
+/// Stake limits of a pool. The default limits only reject empty stakes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct StakeLimits {
+    /// Smallest amount a single stake may request. Stakes of zero tokens are always rejected.
+    pub min_stake: U256,
+    /// Most an address may have staked at once, unless it was given its own cap.
+    pub max_stake_per_address: Option<U256>,
+}
+
+impl StakeLimits {
+    /// Checks the `amount` requested by a single stake.
+    pub fn check_amount(&self, amount: U256) -> Result<(), Error> {
+        if amount.is_zero() || amount < self.min_stake {
+            Err(Error::BelowMinStake)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that staking `accepted` more keeps an address that has `staked` in the pool already
+    /// within `cap`, the cap an admin gave it, or else within the pool-wide maximum.
+    pub fn check_cap(&self, accepted: U256, staked: U256, cap: Option<U256>) -> Result<(), Error> {
+        match cap.or(self.max_stake_per_address) {
+            Some(cap) if staked.safe_add(accepted)? > cap => Err(Error::StakeCapExceeded),
+            _ => Ok(()),
+        }
+    }
+}
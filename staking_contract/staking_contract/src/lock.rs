@@ -0,0 +1,139 @@
+//! Lock-up tiers chosen by stakers and the positions their stakes are recorded as.
+use crate::{error::Error, safe_math};
+use alloc::vec::Vec;
+use casper_types::{
+    bytesrepr::{self, FromBytes, ToBytes},
+    CLType, CLTyped, U256,
+};
+
+/// Reward multipliers are expressed in basis points, so a multiplier of `10_000` is 1x.
+pub const MULTIPLIER_BASIS_POINTS: u64 = 10_000;
+
+/// Most positions a staker may hold tokens in at once, so that the calls going through all of them
+/// stay within the gas limit.
+pub const MAX_LIVE_POSITIONS: usize = 100;
+
+/// A lock duration and the reward multiplier granted to the stakes locked for that long.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LockTier {
+    /// Lock duration in milliseconds of block time.
+    pub duration: u64,
+    /// Reward multiplier in basis points.
+    pub multiplier: u64,
+}
+
+impl LockTier {
+    /// Tier `0` of every pool: no lock and no boost.
+    pub const UNLOCKED: LockTier = LockTier {
+        duration: 0,
+        multiplier: MULTIPLIER_BASIS_POINTS,
+    };
+
+    /// Weight of `amount` staked in this tier, which rewards are shared by.
+    pub fn weight(&self, amount: U256) -> Result<U256, Error> {
+        safe_math::mul_div(
+            amount,
+            U256::from(self.multiplier),
+            U256::from(MULTIPLIER_BASIS_POINTS),
+        )
+    }
+}
+
+impl From<(u64, u64)> for LockTier {
+    fn from((duration, multiplier): (u64, u64)) -> Self {
+        LockTier {
+            duration,
+            multiplier,
+        }
+    }
+}
+
+impl From<LockTier> for (u64, u64) {
+    fn from(lock_tier: LockTier) -> Self {
+        (lock_tier.duration, lock_tier.multiplier)
+    }
+}
+
+/// Returns the tiers of a pool: [`LockTier::UNLOCKED`] followed by the `configured` tiers.
+pub fn lock_tiers(configured: Vec<(u64, u64)>) -> Result<Vec<LockTier>, Error> {
+    if configured.len() >= u8::MAX as usize {
+        return Err(Error::InvalidLockTier);
+    }
+    let mut lock_tiers = Vec::with_capacity(configured.len() + 1);
+    lock_tiers.push(LockTier::UNLOCKED);
+    for lock_tier in configured.into_iter().map(LockTier::from) {
+        if lock_tier.multiplier == 0 {
+            return Err(Error::InvalidLockTier);
+        }
+        lock_tiers.push(lock_tier);
+    }
+    Ok(lock_tiers)
+}
+
+/// Tokens staked in a single `stake` call.
+///
+/// Positions are numbered per staker in the order they were opened and keep their `id` once fully
+/// withdrawn. A position is serialized as its fields in declaration order and typed as
+/// [`CLType::Any`], like the events.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Position {
+    pub id: u64,
+    pub amount: U256,
+    /// Block time at which the position was opened.
+    pub timestamp: u64,
+    pub lock_tier: u8,
+    /// Block time from which the position can be withdrawn.
+    pub unlock_time: u64,
+}
+
+impl Position {
+    pub fn is_locked(&self, now: u64) -> bool {
+        now < self.unlock_time
+    }
+}
+
+impl CLTyped for Position {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+impl ToBytes for Position {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut bytes = Vec::with_capacity(self.serialized_length());
+        bytes.append(&mut self.id.to_bytes()?);
+        bytes.append(&mut self.amount.to_bytes()?);
+        bytes.append(&mut self.timestamp.to_bytes()?);
+        bytes.append(&mut self.lock_tier.to_bytes()?);
+        bytes.append(&mut self.unlock_time.to_bytes()?);
+        Ok(bytes)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.id.serialized_length()
+            + self.amount.serialized_length()
+            + self.timestamp.serialized_length()
+            + self.lock_tier.serialized_length()
+            + self.unlock_time.serialized_length()
+    }
+}
+
+impl FromBytes for Position {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (id, bytes) = u64::from_bytes(bytes)?;
+        let (amount, bytes) = U256::from_bytes(bytes)?;
+        let (timestamp, bytes) = u64::from_bytes(bytes)?;
+        let (lock_tier, bytes) = u8::from_bytes(bytes)?;
+        let (unlock_time, bytes) = u64::from_bytes(bytes)?;
+        Ok((
+            Position {
+                id,
+                amount,
+                timestamp,
+                lock_tier,
+                unlock_time,
+            },
+            bytes,
+        ))
+    }
+}
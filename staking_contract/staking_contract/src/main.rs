@@ -7,17 +7,37 @@ compile_error!("target arch should be wasm32: compile with '--target wasm32-unkn
 extern crate alloc;
 
 use alloc::vec;
-use alloc::{collections::BTreeSet, format, string::String};
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
 use casper_contract::{
     contract_api::{runtime, storage},
     unwrap_or_revert::UnwrapOrRevert,
 };
 use casper_types::{
-    runtime_args, CLTyped, CLValue, ContractPackageHash, EntryPoint, EntryPointAccess,
-    EntryPointType, EntryPoints, Group, Key, Parameter, RuntimeArgs, URef, U256,
+    contracts::NamedKeys, runtime_args, CLTyped, CLValue, ContractPackageHash, EntryPoint,
+    EntryPointAccess, EntryPointType, EntryPoints, Group, Key, Parameter, PublicKey, RuntimeArgs,
+    Signature, URef, U256,
 };
-use contract_utils::{ContractContext, OnChainContractStorage};
-use staking_contract::staking_contract::CEP20STK;
+use contract_utils::{ContractContext, OnChainContractStorage, RoleControl};
+use core::convert::TryFrom;
+use staking_contract::{
+    address::Address,
+    allowlist::Hash,
+    data, detail,
+    error::Error,
+    limit::StakeLimits,
+    lock::{self, LockTier, Position},
+    penalty::{Penalty, PenaltyMode},
+    receipt,
+    reward::RewardMode,
+    role::Role,
+    schedule::Schedule,
+    staking_contract::CEP20STK,
+};
+
+const CONTRACT_PACKAGE_HASH: &str = "contract_package_hash";
+const CONTRACT_ACCESS_UREF: &str = "contract_access_uref";
+const STAKING_CONTRACT_HASH: &str = "staking_contract_hash";
+const MIGRATE_GROUP: &str = "migrate";
 
 #[derive(Default)]
 struct Token(OnChainContractStorage);
@@ -28,6 +48,7 @@ impl ContractContext<OnChainContractStorage> for Token {
     }
 }
 
+impl RoleControl<OnChainContractStorage> for Token {}
 impl CEP20STK<OnChainContractStorage> for Token {}
 impl Token {
     #[allow(clippy::too_many_arguments)]
@@ -40,7 +61,15 @@ impl Token {
         withdraw_starts: u64,
         withdraw_ends: u64,
         staking_total: U256,
+        erc20_contract_package_hash: Key,
+        reward_token_package_hash: Key,
+        reward_mode: RewardMode,
+        lock_tiers: Vec<LockTier>,
+        penalty: Penalty,
+        position_nft: Option<Key>,
+        allowlist_root: Option<Hash>,
     ) {
+        data::init_events();
         CEP20STK::init(
             self,
             name,
@@ -50,7 +79,19 @@ impl Token {
             withdraw_starts,
             withdraw_ends,
             staking_total,
+            erc20_contract_package_hash,
+            reward_token_package_hash,
+            reward_mode,
+            lock_tiers,
+            penalty,
+            position_nft,
+            allowlist_root,
         );
+        // Positions minted as NFTs are not fungible, so they get no receipts.
+        if position_nft.is_none() {
+            self.init_receipt();
+        }
+        self.init_roles(self.get_caller());
     }
 }
 
@@ -66,6 +107,19 @@ pub extern "C" fn constructor() {
     let stacking_contract_package_hash =
         runtime::get_named_arg::<Key>("stacking_contract_package_hash");
     let erc20_contract_package_hash = runtime::get_named_arg::<Key>("erc20_contract_package_hash");
+    let reward_token_package_hash = runtime::get_named_arg::<Key>("reward_token_package_hash");
+    let reward_mode =
+        RewardMode::try_from(runtime::get_named_arg::<u8>("reward_mode")).unwrap_or_revert();
+    let lock_tiers = lock::lock_tiers(runtime::get_named_arg::<Vec<(u64, u64)>>("lock_tiers"))
+        .unwrap_or_revert();
+    let penalty = Penalty::new(
+        runtime::get_named_arg::<u64>("early_withdraw_penalty"),
+        PenaltyMode::try_from(runtime::get_named_arg::<u8>("penalty_mode")).unwrap_or_revert(),
+        runtime::get_named_arg::<Option<Key>>("treasury"),
+    )
+    .unwrap_or_revert();
+    let position_nft = runtime::get_named_arg::<Option<Key>>("position_nft");
+    let allowlist_root = runtime::get_named_arg::<Option<Hash>>("allowlist_root");
 
     #[allow(clippy::useless_conversion)]
     runtime::put_key(
@@ -73,12 +127,6 @@ pub extern "C" fn constructor() {
         stacking_contract_package_hash.into(),
     );
 
-    #[allow(clippy::useless_conversion)]
-    runtime::put_key(
-        "erc20_contract_package_hash",
-        erc20_contract_package_hash.into(),
-    );
-
     Token::default().constructor(
         name,
         address,
@@ -87,6 +135,13 @@ pub extern "C" fn constructor() {
         withdraw_starts,
         withdraw_ends,
         staking_total,
+        erc20_contract_package_hash,
+        reward_token_package_hash,
+        reward_mode,
+        lock_tiers,
+        penalty,
+        position_nft,
+        allowlist_root,
     );
 }
 
@@ -110,7 +165,7 @@ pub extern "C" fn staking_starts() {
 
 #[no_mangle]
 pub extern "C" fn staking_ends() {
-    let ret = Token::default().staking_starts();
+    let ret = Token::default().staking_ends();
     runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
 }
 
@@ -139,302 +194,1385 @@ pub extern "C" fn amount_staked() {
     runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
 }
 
+#[no_mangle]
+pub extern "C" fn positions() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = Token::default().positions(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn position() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let position_id = runtime::get_named_arg::<u64>("position_id");
+    let ret = Token::default()
+        .position(staker, position_id)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn position_count() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = Token::default().position_count(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
 #[no_mangle]
 pub extern "C" fn stake() {
     let amount = runtime::get_named_arg::<U256>("amount");
     let staking_contract_package_hash =
         runtime::get_named_arg::<String>("staking_contract_package_hash");
+    let lock_tier = runtime::get_named_arg::<u8>("lock_tier");
+    let referrer = detail::get_optional_named_arg::<Option<Key>>("referrer").flatten();
     let ret = Token::default()
-        .stake(amount, staking_contract_package_hash)
+        .stake(amount, staking_contract_package_hash, lock_tier, referrer)
         .unwrap_or_revert();
     runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
 }
 
 #[no_mangle]
-pub extern "C" fn withdraw() {
-    let amount = runtime::get_named_arg::<U256>("amount");
-    let ret = Token::default().withdraw(amount).unwrap_or_revert();
+pub extern "C" fn referrer() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = Token::default().referrer(staker);
     runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
 }
 
 #[no_mangle]
-pub extern "C" fn add_reward() {
-    let reward_amount = runtime::get_named_arg::<U256>("reward_amount");
-    let withdrawable_amount = runtime::get_named_arg::<U256>("withdrawable_amount");
+pub extern "C" fn referral_rewards() {
+    let referrer = runtime::get_named_arg::<Key>("referrer");
+    let ret = Token::default().referral_rewards(referrer);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn referral_rate() {
+    let ret = Token::default().referral_rate();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn set_referral_rate() {
+    let referral_rate = runtime::get_named_arg::<u64>("referral_rate");
+    Token::default()
+        .set_referral_rate(referral_rate)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn stake_for() {
+    let beneficiary = runtime::get_named_arg::<Address>("beneficiary");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let staking_contract_package_hash =
+        runtime::get_named_arg::<String>("staking_contract_package_hash");
+    let lock_tier = runtime::get_named_arg::<u8>("lock_tier");
     let ret = Token::default()
-        .add_reward(reward_amount, withdrawable_amount)
+        .stake_for(
+            beneficiary,
+            amount,
+            staking_contract_package_hash,
+            lock_tier,
+        )
         .unwrap_or_revert();
     runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
 }
 
 #[no_mangle]
-pub extern "C" fn get_current_reward() {
-    let ret = Token::default().reward_balance();
+pub extern "C" fn stake_with_permit() {
+    let public_key = runtime::get_named_arg::<PublicKey>("public_key");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let deadline = runtime::get_named_arg::<u64>("deadline");
+    let signature = runtime::get_named_arg::<Signature>("signature");
+    let terms_signature = runtime::get_named_arg::<Signature>("terms_signature");
+    let staking_contract_package_hash =
+        runtime::get_named_arg::<String>("staking_contract_package_hash");
+    let lock_tier = runtime::get_named_arg::<u8>("lock_tier");
+    let ret = Token::default()
+        .stake_with_permit(
+            public_key,
+            amount,
+            deadline,
+            signature,
+            terms_signature,
+            staking_contract_package_hash,
+            lock_tier,
+        )
+        .unwrap_or_revert();
     runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
 }
 
 #[no_mangle]
-pub extern "C" fn staker_reward() {
-    let staker_address = runtime::get_named_arg::<Key>("staker_address");
+pub extern "C" fn stake_with_proof() {
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let staking_contract_package_hash =
+        runtime::get_named_arg::<String>("staking_contract_package_hash");
+    let lock_tier = runtime::get_named_arg::<u8>("lock_tier");
+    let allocation = runtime::get_named_arg::<U256>("allocation");
+    let proof = runtime::get_named_arg::<Vec<Hash>>("proof");
+    let referrer = detail::get_optional_named_arg::<Option<Key>>("referrer").flatten();
     let ret = Token::default()
-        .staker_reward(staker_address)
+        .stake_with_proof(
+            amount,
+            staking_contract_package_hash,
+            lock_tier,
+            allocation,
+            proof,
+            referrer,
+        )
         .unwrap_or_revert();
     runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
 }
 
 #[no_mangle]
-pub extern "C" fn call() {
-    // Read arguments for the constructor call.
-    let name: String = runtime::get_named_arg("name");
-    let address = runtime::get_named_arg::<String>("address");
-    let staking_starts: u64 = runtime::get_named_arg::<u64>("staking_starts");
-    let staking_ends: u64 = runtime::get_named_arg::<u64>("staking_ends");
-    let withdraw_starts: u64 = runtime::get_named_arg::<u64>("withdraw_starts");
-    let withdraw_ends: u64 = runtime::get_named_arg::<u64>("withdraw_ends");
-    let staking_total: U256 = runtime::get_named_arg::<U256>("staking_total");
-    let erc20_contract_package_hash = runtime::get_named_arg::<Key>("erc20_contract_package_hash");
-
-    /*
+pub extern "C" fn allowlist_root() {
+    let ret = Token::default().allowlist_root();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    casper-client put-deploy \
-      --chain-name casper-test \
-      --node-address http://44.208.234.65:7777 \
-      --secret-key ./staking_contract/keys/secret_key.pem \
-      --session-path ./staking_contract/target/wasm32-unknown-unknown/release/staking_contract.wasm \
-      --payment-amount 200000000000 \
-      --session-arg "name:string='FerrumX'" \
-      --session-arg "address:string='782fe4b0bb944e6b1fd2c5a1456a78f0e2193d47dee9b1af5711d6b6e6aaca60'" \
-      --session-arg "staking_starts:u64='0'" \
-      --session-arg "staking_ends:u64='1755994649'" \
-      --session-arg "withdraw_starts:u64='0'" \
-      --session-arg "withdraw_ends:u64='1755994649'" \
-      --session-arg "staking_total:U256='500000'" \
-      --session-arg "erc20_contract_hash:string='contract-2934370f0a53457987d4dba9d68d71e7ee45b676677fbc66653bd15ea58db60f'"
+#[no_mangle]
+pub extern "C" fn set_allowlist_root() {
+    let root = runtime::get_named_arg::<Option<Hash>>("root");
+    Token::default().set_allowlist_root(root).unwrap_or_revert();
+}
 
-        */
-    // casper-client put-deploy \
-    // --chain-name casper-test \
-    // --node-address http://44.208.234.65:7777 \
-    // --secret-key ./staking_contract/keys/secret_key.pem \
-    // --session-path ./target/wasm32-unknown-unknown/release/staking_contract.wasm \
-    // --payment-amount 200000000000 \
-    // --session-arg "name:string='FerrumX'" \
-    // --session-arg "address:string='782fe4b0bb944e6b1fd2c5a1456a78f0e2193d47dee9b1af5711d6b6e6aaca60'" \
-    // --session-arg "staking_starts:u64='0'" \
-    // --session-arg "staking_ends:u64='1755994649'" \
-    // --session-arg "withdraw_starts:u64='0'" \
-    // --session-arg "withdraw_ends:u64='1755994649'" \
-    // --session-arg "staking_total:U256='500000'" \
-    // --session-arg "erc20_contract_hash:string='contract-4277c47789fd154c42471978b4bffec31760f56b07507334a219682cf475181f'"
+#[no_mangle]
+pub extern "C" fn withdraw() {
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let ret = Token::default().withdraw(amount).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    /*
-          casper-client put-deploy \
-          --chain-name casper-test \
-          --node-address http://44.208.234.65:7777 \
-          --secret-key staking_contract/keys/secret_key.pem \
-          --payment-amount 8000000000 \
-          --session-hash 5ec37b076f618f8e3205eda7ad1f8bade197b296716b7e0cbba11a7b7c613bc6 \
-          --session-entry-point "stake" \
-          --session-arg "amount:u256='5'"
-    */
+#[no_mangle]
+pub extern "C" fn withdraw_position() {
+    let position_id = runtime::get_named_arg::<u64>("position_id");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let ret = Token::default()
+        .withdraw_position(position_id, amount)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    // casper-client put-deploy \
-    // --chain-name casper-test \
-    // --node-address http://44.208.234.65:7777 \
-    // --secret-key staking_contract/keys/secret_key.pem \
-    // --session-hash hash-4277c47789fd154c42471978b4bffec31760f56b07507334a219682cf475181f \
-    // --session-entry-point approve \
-    // --payment-amount 20000000000 \
-    // --session-arg "spender:key='hash-153fc70a20eeefb8b6a02b217b6a19cc8439092569750ced554bde9d3d8f9896'" \
-    // --session-arg "amount:u256='1000000000'"
+#[no_mangle]
+pub extern "C" fn withdraw_nft() {
+    let token_id = runtime::get_named_arg::<u64>("token_id");
+    let ret = Token::default().withdraw_nft(token_id).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    /*
-          casper-client put-deploy \
-          --chain-name casper-test \
-          --node-address http://159.65.118.250:7777 \
-          --secret-key cep47/keys/secret_key.pem \
-          --payment-amount 4000000000 \
-          --session-hash 6adb2902bf7c56116ead7ea7a2ffa269b8d4b117b632d2c44052f3c951dcaa0b \
-          --session-entry-point "withdraw" \
-          --session-arg "amount:u256='10'"
-    */
+#[no_mangle]
+pub extern "C" fn position_token_id() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let position_id = runtime::get_named_arg::<u64>("position_id");
+    let ret = Token::default().position_token_id(staker, position_id);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    /*
-          casper-client put-deploy \
-          --chain-name casper-test \
-          --node-address http://159.65.118.250:7777 \
-          --secret-key cep47/keys/secret_key.pem \
-          --payment-amount 4000000000 \
-          --session-hash 6adb2902bf7c56116ead7ea7a2ffa269b8d4b117b632d2c44052f3c951dcaa0b \
-          --session-entry-point "add_reward" \
-          --session-arg "reward_amount:u256='20'" \
-          --session-arg "withdrawable_amount:u256='19'"
-    */
+#[no_mangle]
+pub extern "C" fn position_nft() {
+    let ret = Token::default().position_nft();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    /*
-    casper-client get-deploy \
-        --id 2 \
-        --node-address http://159.65.118.250:7777 \
-        ee07324ad466aad373e94f787b3dbf1ba1ff00175e97a0bce002bb45737ad5e6
+#[no_mangle]
+pub extern "C" fn claim_rewards() {
+    let ret = Token::default().claim_rewards().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    */
+#[no_mangle]
+pub extern "C" fn claim_rewards_nft() {
+    let token_id = runtime::get_named_arg::<u64>("token_id");
+    let ret = Token::default()
+        .claim_rewards_nft(token_id)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    let (contract_hash, _) = storage::new_contract(
-        get_entry_points(),
-        None,
-        Some(String::from("contract_package_hash")),
-        None,
-    );
+#[no_mangle]
+pub extern "C" fn withdraw_for() {
+    let staker = runtime::get_named_arg::<Address>("staker");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let ret = Token::default()
+        .withdraw_for(staker, amount)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    let package_hash: ContractPackageHash = ContractPackageHash::new(
-        runtime::get_key("contract_package_hash")
-            .unwrap_or_revert()
-            .into_hash()
-            .unwrap_or_revert(),
-    );
+#[no_mangle]
+pub extern "C" fn withdraw_position_for() {
+    let staker = runtime::get_named_arg::<Address>("staker");
+    let position_id = runtime::get_named_arg::<u64>("position_id");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    let ret = Token::default()
+        .withdraw_position_for(staker, position_id, amount)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    let package_hash_key: Key = package_hash.into();
+#[no_mangle]
+pub extern "C" fn claim_rewards_for() {
+    let staker = runtime::get_named_arg::<Address>("staker");
+    let ret = Token::default()
+        .claim_rewards_for(staker)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    // Prepare constructor args
-    let constructor_args = runtime_args! {
-        "name" => name,
-        "address" => address,
-        "staking_starts" => staking_starts,
-        "staking_ends" => staking_ends,
-        "withdraw_starts" => withdraw_starts,
-        "withdraw_ends" => withdraw_ends,
-        "staking_total" => staking_total,
-        "stacking_contract_package_hash" => package_hash_key,
-        "erc20_contract_package_hash" => erc20_contract_package_hash,
-    };
+#[no_mangle]
+pub extern "C" fn approve_operator() {
+    let operator = runtime::get_named_arg::<Key>("operator");
+    Token::default()
+        .approve_operator(operator)
+        .unwrap_or_revert();
+}
 
-    let constructor_access: URef =
-        storage::create_contract_user_group(package_hash, "constructor", 1, Default::default())
-            .unwrap_or_revert()
-            .pop()
-            .unwrap_or_revert();
+#[no_mangle]
+pub extern "C" fn revoke_operator() {
+    let operator = runtime::get_named_arg::<Key>("operator");
+    Token::default()
+        .revoke_operator(operator)
+        .unwrap_or_revert();
+}
 
-    let _: () = runtime::call_contract(contract_hash, "constructor", constructor_args);
+#[no_mangle]
+pub extern "C" fn is_operator() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let operator = runtime::get_named_arg::<Key>("operator");
+    let ret = Token::default().is_operator(staker, operator);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
 
-    let mut urefs = BTreeSet::new();
-    urefs.insert(constructor_access);
-    storage::remove_contract_user_group_urefs(package_hash, "constructor", urefs)
+#[no_mangle]
+pub extern "C" fn set_payout_destination() {
+    let destination = runtime::get_named_arg::<Option<Key>>("destination");
+    Token::default()
+        .set_payout_destination(destination)
         .unwrap_or_revert();
+}
 
-    runtime::put_key("staking_contract_hash", contract_hash.into());
-    runtime::put_key(
-        &format!("{contract_hash}_contract_hash_wrapped"),
-        storage::new_uref(contract_hash).into(),
-    );
+#[no_mangle]
+pub extern "C" fn payout_destination() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = Token::default().payout_destination(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
 }
 
-fn get_entry_points() -> EntryPoints {
-    let mut entry_points = EntryPoints::new();
-    entry_points.add_entry_point(EntryPoint::new(
-        "constructor",
-        vec![
-            Parameter::new("name", String::cl_type()),
+#[no_mangle]
+pub extern "C" fn lock_tiers() {
+    let ret: Vec<(u64, u64)> = Token::default()
+        .lock_tiers()
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn penalty() {
+    let penalty = Token::default().penalty();
+    let ret = (penalty.rate, penalty.mode as u8, penalty.treasury);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn claimed_rewards() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = Token::default().claimed_rewards(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn reward_token_package_hash() {
+    let ret = Token::default().reward_token_package_hash();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn paused() {
+    let ret = Token::default().paused();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn phase() {
+    let ret = Token::default().phase() as u8;
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn reschedule() {
+    let schedule = Schedule {
+        staking_starts: runtime::get_named_arg::<u64>("staking_starts"),
+        staking_ends: runtime::get_named_arg::<u64>("staking_ends"),
+        withdraw_starts: runtime::get_named_arg::<u64>("withdraw_starts"),
+        withdraw_ends: runtime::get_named_arg::<u64>("withdraw_ends"),
+    };
+    Token::default().reschedule(schedule).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn min_stake() {
+    let ret = Token::default().stake_limits().min_stake;
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn max_stake_per_address() {
+    let ret = Token::default().stake_limits().max_stake_per_address;
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn set_stake_limits() {
+    let stake_limits = StakeLimits {
+        min_stake: runtime::get_named_arg::<U256>("min_stake"),
+        max_stake_per_address: runtime::get_named_arg::<Option<U256>>("max_stake_per_address"),
+    };
+    Token::default()
+        .set_stake_limits(stake_limits)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn stake_cap() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = Token::default().stake_cap(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn set_stake_cap() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let cap = runtime::get_named_arg::<Option<U256>>("cap");
+    Token::default()
+        .set_stake_cap(staker, cap)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn pause() {
+    Token::default().pause().unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn unpause() {
+    Token::default().unpause().unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn emergency_withdraw() {
+    let ret = Token::default().emergency_withdraw().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn emergency_withdraw_nft() {
+    let token_id = runtime::get_named_arg::<u64>("token_id");
+    let ret = Token::default()
+        .emergency_withdraw_nft(token_id)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn dust() {
+    let ret = Token::default().dust();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn sweep_dust() {
+    let ret = Token::default().sweep_dust().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn symbol() {
+    let ret = receipt::symbol().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn decimals() {
+    let ret = receipt::decimals().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn total_supply() {
+    let ret = receipt::total_supply();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn balance_of() {
+    let address = runtime::get_named_arg::<Address>("address");
+    let ret = receipt::balance_of(address).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn allowance() {
+    let owner = runtime::get_named_arg::<Address>("owner");
+    let spender = runtime::get_named_arg::<Address>("spender");
+    let ret = receipt::allowance(owner, spender).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn approve() {
+    let spender = runtime::get_named_arg::<Address>("spender");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    receipt::approve(spender, amount).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn transfer() {
+    let recipient = runtime::get_named_arg::<Address>("recipient");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    Token::default()
+        .transfer_receipt(recipient, amount)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn transfer_from() {
+    let owner = runtime::get_named_arg::<Address>("owner");
+    let recipient = runtime::get_named_arg::<Address>("recipient");
+    let amount = runtime::get_named_arg::<U256>("amount");
+    Token::default()
+        .transfer_receipt_from(owner, recipient, amount)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn compound() {
+    let ret = Token::default().compound().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn shares_of() {
+    let staker = runtime::get_named_arg::<Key>("staker");
+    let ret = Token::default().shares_of(staker);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn price_per_share() {
+    let ret = Token::default().price_per_share().unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn redeem() {
+    let shares = runtime::get_named_arg::<U256>("shares");
+    let ret = Token::default().redeem(shares).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn add_reward() {
+    let reward_amount = runtime::get_named_arg::<U256>("reward_amount");
+    let withdrawable_amount = runtime::get_named_arg::<U256>("withdrawable_amount");
+    let ret = Token::default()
+        .add_reward(reward_amount, withdrawable_amount)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_current_reward() {
+    let ret = Token::default().reward_balance();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn staker_reward() {
+    let staker_address = runtime::get_named_arg::<Key>("staker_address");
+    let ret = Token::default()
+        .staker_reward(staker_address)
+        .unwrap_or_revert();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn grant_role() {
+    let role = Role::try_from(runtime::get_named_arg::<u8>("role")).unwrap_or_revert();
+    let account = runtime::get_named_arg::<Key>("account");
+    Token::default()
+        .grant_role(role, account)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn revoke_role() {
+    let role = Role::try_from(runtime::get_named_arg::<u8>("role")).unwrap_or_revert();
+    let account = runtime::get_named_arg::<Key>("account");
+    Token::default()
+        .revoke_role(role, account)
+        .unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn renounce_role() {
+    let role = Role::try_from(runtime::get_named_arg::<u8>("role")).unwrap_or_revert();
+    Token::default().renounce_role(role).unwrap_or_revert();
+}
+
+#[no_mangle]
+pub extern "C" fn has_role() {
+    let role = Role::try_from(runtime::get_named_arg::<u8>("role")).unwrap_or_revert();
+    let account = runtime::get_named_arg::<Key>("account");
+    let ret = RoleControl::has_role(&Token::default(), role as u8, account);
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn schema_version() {
+    let ret = Token::default().schema_version();
+    runtime::ret(CLValue::from_t(ret).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn migrate() {
+    Token::default().migrate().unwrap_or_revert();
+}
+
+/// Installs the contract as a new package, or upgrades the package installed by the deploying
+/// account when called with `upgrade` set to true.
+#[no_mangle]
+pub extern "C" fn call() {
+    if detail::get_optional_named_arg::<bool>("upgrade").unwrap_or_default() {
+        upgrade();
+    } else {
+        install();
+    }
+}
+
+/// Adds a new version to the existing package. The new version inherits the named keys of the
+/// previous one, so staker balances, windows and reward state are kept as they are.
+///
+/// Only the holder of the package access URef can upgrade. It lets the session create the group
+/// allowed to call `migrate` for the duration of the upgrade.
+fn upgrade() {
+    runtime::get_key(CONTRACT_ACCESS_UREF).unwrap_or_revert_with(Error::PermissionDenied);
+    let package_hash: ContractPackageHash = ContractPackageHash::new(
+        runtime::get_key(CONTRACT_PACKAGE_HASH)
+            .unwrap_or_revert()
+            .into_hash()
+            .unwrap_or_revert(),
+    );
+
+    let (contract_hash, _) =
+        storage::add_contract_version(package_hash, get_entry_points(), NamedKeys::new());
+
+    let _migrate_access: Vec<URef> =
+        storage::create_contract_user_group(package_hash, MIGRATE_GROUP, 1, Default::default())
+            .unwrap_or_revert();
+
+    let _: () = runtime::call_contract(contract_hash, "migrate", runtime_args! {});
+
+    storage::remove_contract_user_group(package_hash, MIGRATE_GROUP).unwrap_or_revert();
+
+    runtime::put_key(STAKING_CONTRACT_HASH, contract_hash.into());
+    runtime::put_key(
+        &format!("{contract_hash}_contract_hash_wrapped"),
+        storage::new_uref(contract_hash).into(),
+    );
+}
+
+fn install() {
+    // Read arguments for the constructor call.
+    let name: String = runtime::get_named_arg("name");
+    let address = runtime::get_named_arg::<String>("address");
+    let staking_starts: u64 = runtime::get_named_arg::<u64>("staking_starts");
+    let staking_ends: u64 = runtime::get_named_arg::<u64>("staking_ends");
+    let withdraw_starts: u64 = runtime::get_named_arg::<u64>("withdraw_starts");
+    let withdraw_ends: u64 = runtime::get_named_arg::<u64>("withdraw_ends");
+    let staking_total: U256 = runtime::get_named_arg::<U256>("staking_total");
+    let erc20_contract_package_hash = runtime::get_named_arg::<Key>("erc20_contract_package_hash");
+    let reward_token_package_hash = runtime::get_named_arg::<Key>("reward_token_package_hash");
+    let reward_mode: u8 = runtime::get_named_arg::<u8>("reward_mode");
+    let lock_tiers = runtime::get_named_arg::<Vec<(u64, u64)>>("lock_tiers");
+    let early_withdraw_penalty = runtime::get_named_arg::<u64>("early_withdraw_penalty");
+    let penalty_mode = runtime::get_named_arg::<u8>("penalty_mode");
+    let treasury = runtime::get_named_arg::<Option<Key>>("treasury");
+    let position_nft = runtime::get_named_arg::<Option<Key>>("position_nft");
+    let allowlist_root = runtime::get_named_arg::<Option<Hash>>("allowlist_root");
+
+    /*
+
+    casper-client put-deploy \
+      --chain-name casper-test \
+      --node-address http://44.208.234.65:7777 \
+      --secret-key ./staking_contract/keys/secret_key.pem \
+      --session-path ./staking_contract/target/wasm32-unknown-unknown/release/staking_contract.wasm \
+      --payment-amount 200000000000 \
+      --session-arg "name:string='FerrumX'" \
+      --session-arg "address:string='782fe4b0bb944e6b1fd2c5a1456a78f0e2193d47dee9b1af5711d6b6e6aaca60'" \
+      --session-arg "staking_starts:u64='0'" \
+      --session-arg "staking_ends:u64='1755994649'" \
+      --session-arg "withdraw_starts:u64='0'" \
+      --session-arg "withdraw_ends:u64='1755994649'" \
+      --session-arg "staking_total:U256='500000'" \
+      --session-arg "erc20_contract_hash:string='contract-2934370f0a53457987d4dba9d68d71e7ee45b676677fbc66653bd15ea58db60f'"
+
+        */
+    // casper-client put-deploy \
+    // --chain-name casper-test \
+    // --node-address http://44.208.234.65:7777 \
+    // --secret-key ./staking_contract/keys/secret_key.pem \
+    // --session-path ./target/wasm32-unknown-unknown/release/staking_contract.wasm \
+    // --payment-amount 200000000000 \
+    // --session-arg "name:string='FerrumX'" \
+    // --session-arg "address:string='782fe4b0bb944e6b1fd2c5a1456a78f0e2193d47dee9b1af5711d6b6e6aaca60'" \
+    // --session-arg "staking_starts:u64='0'" \
+    // --session-arg "staking_ends:u64='1755994649'" \
+    // --session-arg "withdraw_starts:u64='0'" \
+    // --session-arg "withdraw_ends:u64='1755994649'" \
+    // --session-arg "staking_total:U256='500000'" \
+    // --session-arg "erc20_contract_hash:string='contract-4277c47789fd154c42471978b4bffec31760f56b07507334a219682cf475181f'"
+
+    /*
+          casper-client put-deploy \
+          --chain-name casper-test \
+          --node-address http://44.208.234.65:7777 \
+          --secret-key staking_contract/keys/secret_key.pem \
+          --payment-amount 8000000000 \
+          --session-hash 5ec37b076f618f8e3205eda7ad1f8bade197b296716b7e0cbba11a7b7c613bc6 \
+          --session-entry-point "stake" \
+          --session-arg "amount:u256='5'"
+    */
+
+    // casper-client put-deploy \
+    // --chain-name casper-test \
+    // --node-address http://44.208.234.65:7777 \
+    // --secret-key staking_contract/keys/secret_key.pem \
+    // --session-hash hash-4277c47789fd154c42471978b4bffec31760f56b07507334a219682cf475181f \
+    // --session-entry-point approve \
+    // --payment-amount 20000000000 \
+    // --session-arg "spender:key='hash-153fc70a20eeefb8b6a02b217b6a19cc8439092569750ced554bde9d3d8f9896'" \
+    // --session-arg "amount:u256='1000000000'"
+
+    /*
+          casper-client put-deploy \
+          --chain-name casper-test \
+          --node-address http://159.65.118.250:7777 \
+          --secret-key cep47/keys/secret_key.pem \
+          --payment-amount 4000000000 \
+          --session-hash 6adb2902bf7c56116ead7ea7a2ffa269b8d4b117b632d2c44052f3c951dcaa0b \
+          --session-entry-point "withdraw" \
+          --session-arg "amount:u256='10'"
+    */
+
+    /*
+          casper-client put-deploy \
+          --chain-name casper-test \
+          --node-address http://159.65.118.250:7777 \
+          --secret-key cep47/keys/secret_key.pem \
+          --payment-amount 4000000000 \
+          --session-hash 6adb2902bf7c56116ead7ea7a2ffa269b8d4b117b632d2c44052f3c951dcaa0b \
+          --session-entry-point "add_reward" \
+          --session-arg "reward_amount:u256='20'" \
+          --session-arg "withdrawable_amount:u256='19'"
+    */
+
+    /*
+    casper-client get-deploy \
+        --id 2 \
+        --node-address http://159.65.118.250:7777 \
+        ee07324ad466aad373e94f787b3dbf1ba1ff00175e97a0bce002bb45737ad5e6
+
+    */
+
+    let (contract_hash, _) = storage::new_contract(
+        get_entry_points(),
+        None,
+        Some(String::from(CONTRACT_PACKAGE_HASH)),
+        Some(String::from(CONTRACT_ACCESS_UREF)),
+    );
+
+    let package_hash: ContractPackageHash = ContractPackageHash::new(
+        runtime::get_key(CONTRACT_PACKAGE_HASH)
+            .unwrap_or_revert()
+            .into_hash()
+            .unwrap_or_revert(),
+    );
+
+    let package_hash_key: Key = package_hash.into();
+
+    // Prepare constructor args
+    let constructor_args = runtime_args! {
+        "name" => name,
+        "address" => address,
+        "staking_starts" => staking_starts,
+        "staking_ends" => staking_ends,
+        "withdraw_starts" => withdraw_starts,
+        "withdraw_ends" => withdraw_ends,
+        "staking_total" => staking_total,
+        "stacking_contract_package_hash" => package_hash_key,
+        "erc20_contract_package_hash" => erc20_contract_package_hash,
+        "reward_token_package_hash" => reward_token_package_hash,
+        "reward_mode" => reward_mode,
+        "lock_tiers" => lock_tiers,
+        "early_withdraw_penalty" => early_withdraw_penalty,
+        "penalty_mode" => penalty_mode,
+        "treasury" => treasury,
+        "position_nft" => position_nft,
+        "allowlist_root" => allowlist_root,
+    };
+
+    let constructor_access: URef =
+        storage::create_contract_user_group(package_hash, "constructor", 1, Default::default())
+            .unwrap_or_revert()
+            .pop()
+            .unwrap_or_revert();
+
+    let _: () = runtime::call_contract(contract_hash, "constructor", constructor_args);
+
+    let mut urefs = BTreeSet::new();
+    urefs.insert(constructor_access);
+    storage::remove_contract_user_group_urefs(package_hash, "constructor", urefs)
+        .unwrap_or_revert();
+
+    runtime::put_key(STAKING_CONTRACT_HASH, contract_hash.into());
+    runtime::put_key(
+        &format!("{contract_hash}_contract_hash_wrapped"),
+        storage::new_uref(contract_hash).into(),
+    );
+}
+
+fn get_entry_points() -> EntryPoints {
+    let mut entry_points = EntryPoints::new();
+    entry_points.add_entry_point(EntryPoint::new(
+        "constructor",
+        vec![
+            Parameter::new("name", String::cl_type()),
             Parameter::new("address", String::cl_type()),
             Parameter::new("staking_starts", u64::cl_type()),
             Parameter::new("staking_ends", u64::cl_type()),
             Parameter::new("withdraw_starts", u64::cl_type()),
             Parameter::new("withdraw_ends", u64::cl_type()),
-            Parameter::new("staking_total", U256::cl_type()),
-            Parameter::new("erc20_contract_package_hash", String::cl_type()),
+            Parameter::new("staking_total", U256::cl_type()),
+            Parameter::new("erc20_contract_package_hash", String::cl_type()),
+            Parameter::new("reward_token_package_hash", Key::cl_type()),
+            Parameter::new("reward_mode", u8::cl_type()),
+            Parameter::new("lock_tiers", Vec::<(u64, u64)>::cl_type()),
+            Parameter::new("early_withdraw_penalty", u64::cl_type()),
+            Parameter::new("penalty_mode", u8::cl_type()),
+            Parameter::new("treasury", Option::<Key>::cl_type()),
+            Parameter::new("position_nft", Option::<Key>::cl_type()),
+            Parameter::new("allowlist_root", Option::<Hash>::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Groups(vec![Group::new("constructor")]),
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "name",
+        vec![],
+        String::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "address",
+        vec![],
+        String::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "staking_starts",
+        vec![],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "staking_ends",
+        vec![],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "withdraw_starts",
+        vec![],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "withdraw_ends",
+        vec![],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "staking_total",
+        vec![],
+        U256::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "withdraw",
+        vec![Parameter::new("amount", Key::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "stake",
+        vec![
+            Parameter::new("amount", U256::cl_type()),
+            Parameter::new("staking_contract_package_hash", String::cl_type()),
+            Parameter::new("lock_tier", u8::cl_type()),
+            Parameter::new("referrer", Option::<Key>::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "referrer",
+        vec![Parameter::new("staker", Key::cl_type())],
+        Option::<Key>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "referral_rewards",
+        vec![Parameter::new("referrer", Key::cl_type())],
+        U256::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "referral_rate",
+        vec![],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_referral_rate",
+        vec![Parameter::new("referral_rate", u64::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "stake_for",
+        vec![
+            Parameter::new("beneficiary", Key::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+            Parameter::new("staking_contract_package_hash", String::cl_type()),
+            Parameter::new("lock_tier", u8::cl_type()),
+        ],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "stake_with_permit",
+        vec![
+            Parameter::new("public_key", PublicKey::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+            Parameter::new("deadline", u64::cl_type()),
+            Parameter::new("signature", Signature::cl_type()),
+            Parameter::new("terms_signature", Signature::cl_type()),
+            Parameter::new("staking_contract_package_hash", String::cl_type()),
+            Parameter::new("lock_tier", u8::cl_type()),
+        ],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "stake_with_proof",
+        vec![
+            Parameter::new("amount", U256::cl_type()),
+            Parameter::new("staking_contract_package_hash", String::cl_type()),
+            Parameter::new("lock_tier", u8::cl_type()),
+            Parameter::new("allocation", U256::cl_type()),
+            Parameter::new("proof", Vec::<Hash>::cl_type()),
+            Parameter::new("referrer", Option::<Key>::cl_type()),
+        ],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "allowlist_root",
+        vec![],
+        Option::<Hash>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_allowlist_root",
+        vec![Parameter::new("root", Option::<Hash>::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "amount_staked",
+        vec![Parameter::new("staker", Key::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "positions",
+        vec![Parameter::new("staker", Key::cl_type())],
+        Vec::<Position>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "position",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("position_id", u64::cl_type()),
+        ],
+        Position::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "position_count",
+        vec![Parameter::new("staker", Key::cl_type())],
+        u64::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "withdraw_position",
+        vec![
+            Parameter::new("position_id", u64::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+        ],
+        U256::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "add_reward",
+        vec![
+            Parameter::new("reward_amount", Key::cl_type()),
+            Parameter::new("withdrawable_amount", Key::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "withdraw_nft",
+        vec![Parameter::new("token_id", u64::cl_type())],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "position_token_id",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("position_id", u64::cl_type()),
+        ],
+        Option::<u64>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "position_nft",
+        vec![],
+        Option::<Key>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "claim_rewards",
+        vec![],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "claim_rewards_nft",
+        vec![Parameter::new("token_id", u64::cl_type())],
+        U256::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "withdraw_for",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+        ],
+        U256::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "withdraw_position_for",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("position_id", u64::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+        ],
+        U256::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "claim_rewards_for",
+        vec![Parameter::new("staker", Key::cl_type())],
+        U256::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "approve_operator",
+        vec![Parameter::new("operator", Key::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "revoke_operator",
+        vec![Parameter::new("operator", Key::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "is_operator",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("operator", Key::cl_type()),
+        ],
+        bool::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_payout_destination",
+        vec![Parameter::new("destination", Option::<Key>::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "payout_destination",
+        vec![Parameter::new("staker", Key::cl_type())],
+        Option::<Key>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "lock_tiers",
+        vec![],
+        Vec::<(u64, u64)>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "penalty",
+        vec![],
+        <(u64, u8, Option<Key>)>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "claimed_rewards",
+        vec![Parameter::new("staker", Key::cl_type())],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "reward_token_package_hash",
+        vec![],
+        Key::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "get_current_reward",
+        vec![],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "staker_reward",
+        vec![Parameter::new("staker_address", Key::cl_type())],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "schema_version",
+        vec![],
+        u32::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "migrate",
+        vec![],
+        <()>::cl_type(),
+        EntryPointAccess::Groups(vec![Group::new(MIGRATE_GROUP)]),
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "paused",
+        vec![],
+        bool::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "phase",
+        vec![],
+        u8::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "reschedule",
+        vec![
+            Parameter::new("staking_starts", u64::cl_type()),
+            Parameter::new("staking_ends", u64::cl_type()),
+            Parameter::new("withdraw_starts", u64::cl_type()),
+            Parameter::new("withdraw_ends", u64::cl_type()),
         ],
         <()>::cl_type(),
-        EntryPointAccess::Groups(vec![Group::new("constructor")]),
+        EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "name",
+        "min_stake",
         vec![],
-        String::cl_type(),
+        U256::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "address",
+        "max_stake_per_address",
         vec![],
-        String::cl_type(),
+        Option::<U256>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "staking_starts",
+        "set_stake_limits",
+        vec![
+            Parameter::new("min_stake", U256::cl_type()),
+            Parameter::new("max_stake_per_address", Option::<U256>::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "stake_cap",
+        vec![Parameter::new("staker", Key::cl_type())],
+        Option::<U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "set_stake_cap",
+        vec![
+            Parameter::new("staker", Key::cl_type()),
+            Parameter::new("cap", Option::<U256>::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "pause",
         vec![],
-        u64::cl_type(),
+        <()>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "withdraw_starts",
+        "unpause",
         vec![],
-        u64::cl_type(),
+        <()>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "withdraw_ends",
+        "emergency_withdraw",
         vec![],
-        u64::cl_type(),
+        <U256>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "staking_total",
+        "emergency_withdraw_nft",
+        vec![Parameter::new("token_id", u64::cl_type())],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "dust",
         vec![],
-        U256::cl_type(),
+        <U256>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "withdraw",
-        vec![Parameter::new("amount", Key::cl_type())],
-        <()>::cl_type(),
+        "sweep_dust",
+        vec![],
+        <U256>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "stake",
+        "symbol",
+        vec![],
+        String::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "decimals",
+        vec![],
+        u8::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "total_supply",
+        vec![],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "balance_of",
+        vec![Parameter::new("address", Key::cl_type())],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "allowance",
+        vec![
+            Parameter::new("owner", Key::cl_type()),
+            Parameter::new("spender", Key::cl_type()),
+        ],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "approve",
         vec![
+            Parameter::new("spender", Key::cl_type()),
             Parameter::new("amount", U256::cl_type()),
-            Parameter::new("staking_contract_package_hash", String::cl_type()),
         ],
         <()>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "amount_staked",
-        vec![Parameter::new("staker", Key::cl_type())],
+        "transfer",
+        vec![
+            Parameter::new("recipient", Key::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
+        ],
         <()>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "add_reward",
+        "transfer_from",
         vec![
-            Parameter::new("reward_amount", Key::cl_type()),
-            Parameter::new("withdrawable_amount", Key::cl_type()),
+            Parameter::new("owner", Key::cl_type()),
+            Parameter::new("recipient", Key::cl_type()),
+            Parameter::new("amount", U256::cl_type()),
         ],
         <()>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "get_current_reward",
+        "compound",
         vec![],
         <U256>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
     entry_points.add_entry_point(EntryPoint::new(
-        "staker_reward",
-        vec![Parameter::new("staker_address", Key::cl_type())],
+        "shares_of",
+        vec![Parameter::new("staker", Key::cl_type())],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "price_per_share",
+        vec![],
+        <U256>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "redeem",
+        vec![Parameter::new("shares", U256::cl_type())],
         <U256>::cl_type(),
         EntryPointAccess::Public,
         EntryPointType::Contract,
     ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "grant_role",
+        vec![
+            Parameter::new("role", u8::cl_type()),
+            Parameter::new("account", Key::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "revoke_role",
+        vec![
+            Parameter::new("role", u8::cl_type()),
+            Parameter::new("account", Key::cl_type()),
+        ],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "renounce_role",
+        vec![Parameter::new("role", u8::cl_type())],
+        <()>::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
+    entry_points.add_entry_point(EntryPoint::new(
+        "has_role",
+        vec![
+            Parameter::new("role", u8::cl_type()),
+            Parameter::new("account", Key::cl_type()),
+        ],
+        bool::cl_type(),
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    ));
     entry_points
 }
@@ -1,6 +1,11 @@
-use crate::error::Error;
+use crate::{
+    data,
+    error::Error,
+    phase::{Operation, PoolPhase},
+    schedule::Schedule,
+};
 use casper_contract::contract_api::runtime;
-use casper_types::{BlockTime, U256};
+use casper_types::U256;
 
 pub fn positive(amount: U256) -> Result<(), Error> {
     if amount <= U256::from(0) {
@@ -10,17 +15,51 @@ pub fn positive(amount: U256) -> Result<(), Error> {
     }
 }
 
-pub fn after(event_time: u64) -> Result<(), Error> {
-    if runtime::get_blocktime() < BlockTime::new(event_time) {
+/// Current phase of the pool.
+pub fn phase() -> PoolPhase {
+    let schedule = Schedule {
+        staking_starts: data::staking_starts(),
+        staking_ends: data::staking_ends(),
+        withdraw_starts: data::withdraw_starts(),
+        withdraw_ends: data::withdraw_ends(),
+    };
+    PoolPhase::at(
+        u64::from(runtime::get_blocktime()),
+        &schedule,
+        data::paused(),
+    )
+}
+
+/// Guard of every entry point changing the pool: `operation` must be allowed in the current phase
+/// by [`Operation::allowed_phases`]. Fails with `Paused` or `NotPaused` when the pause state is what
+/// is wrong, and with `BadTiming` otherwise.
+pub fn in_phase(operation: Operation) -> Result<(), Error> {
+    let phase = phase();
+    let reward_mode = data::reward_mode();
+    if operation.is_allowed(phase, reward_mode) {
+        Ok(())
+    } else if phase == PoolPhase::Paused {
+        Err(Error::Paused)
+    } else if operation.is_allowed(PoolPhase::Paused, reward_mode) {
+        Err(Error::NotPaused)
+    } else {
         Err(Error::BadTiming)
+    }
+}
+
+/// Stakes of pools minting position NFTs are only withdrawn by redeeming their NFT.
+pub fn without_position_nft() -> Result<(), Error> {
+    if data::position_nft().is_some() {
+        Err(Error::PositionNftRequired)
     } else {
         Ok(())
     }
 }
 
-pub fn before(event_time: u64) -> Result<(), Error> {
-    if runtime::get_blocktime() >= BlockTime::new(event_time) {
-        Err(Error::BadTiming)
+/// Stakes of pools restricted to an allowlist stay with the addresses that proved their allocation.
+pub fn without_allowlist() -> Result<(), Error> {
+    if data::allowlist_root().is_some() {
+        Err(Error::NotAllowlisted)
     } else {
         Ok(())
     }
@@ -0,0 +1,89 @@
+//! Penalty charged on the principal withdrawn before `withdraw_ends`.
+use crate::{
+    address::Address,
+    error::Error,
+    safe_math::{self, SafeMath},
+};
+use casper_types::{Key, U256};
+use core::convert::TryFrom;
+
+/// Penalty rates are expressed in basis points of the withdrawn principal.
+pub const PENALTY_BASIS_POINTS: u64 = 10_000;
+
+/// Where the tokens forfeited by early withdrawals go.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PenaltyMode {
+    /// Paid to the treasury account of the pool.
+    Treasury = 0,
+    /// Added to the rewards of the stakers left in the pool.
+    Redistribute = 1,
+    /// Burned from the supply of the staking token.
+    Burn = 2,
+}
+
+impl TryFrom<u8> for PenaltyMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PenaltyMode::Treasury),
+            1 => Ok(PenaltyMode::Redistribute),
+            2 => Ok(PenaltyMode::Burn),
+            _ => Err(Error::InvalidPenalty),
+        }
+    }
+}
+
+/// Early withdrawal penalty of a pool.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Penalty {
+    /// Penalty rate in basis points, charged in full until `staking_ends`.
+    pub rate: u64,
+    pub mode: PenaltyMode,
+    /// Recipient of the forfeited tokens in [`PenaltyMode::Treasury`] mode.
+    pub treasury: Option<Address>,
+}
+
+impl Penalty {
+    pub fn new(rate: u64, mode: PenaltyMode, treasury: Option<Key>) -> Result<Self, Error> {
+        let treasury = treasury
+            .map(Address::try_from)
+            .transpose()
+            .map_err(|_| Error::InvalidPenalty)?;
+        if rate > PENALTY_BASIS_POINTS
+            || (rate > 0 && mode == PenaltyMode::Treasury && treasury.is_none())
+        {
+            return Err(Error::InvalidPenalty);
+        }
+        Ok(Penalty {
+            rate,
+            mode,
+            treasury,
+        })
+    }
+}
+
+/// Returns the part of `amount` forfeited when withdrawing at `now`.
+///
+/// The full `rate` applies until `staking_ends`, then decays linearly to zero at `withdraw_ends`.
+pub fn early_withdraw_penalty(
+    amount: U256,
+    rate: u64,
+    now: u64,
+    staking_ends: u64,
+    withdraw_ends: u64,
+) -> Result<U256, Error> {
+    if now >= withdraw_ends {
+        return Ok(U256::zero());
+    }
+    let penalty = safe_math::mul_div(amount, U256::from(rate), U256::from(PENALTY_BASIS_POINTS))?;
+    if now <= staking_ends || withdraw_ends <= staking_ends {
+        return Ok(penalty);
+    }
+    safe_math::mul_div(
+        penalty,
+        U256::from(withdraw_ends.safe_sub(now)?),
+        U256::from(withdraw_ends.safe_sub(staking_ends)?),
+    )
+}
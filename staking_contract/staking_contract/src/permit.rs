@@ -0,0 +1,33 @@
+//! Stake terms signed along with a permit of the staking token.
+//!
+//! The permit only approves the pool to take `amount` of the tokens of its owner. For anyone to be
+//! able to submit `stake_with_permit`, the owner also signs the blake2b digest of
+//! [`stake_terms_preimage`], which ties the lock tier it picked to the pool and to the signature of
+//! that permit. As the token consumes a nonce with every permit, the terms can not be replayed.
+use crate::error::Error;
+use alloc::vec::Vec;
+use casper_types::{bytesrepr::ToBytes, Key, Signature};
+
+/// Prefix of every stake terms preimage, so that its signature can not be replayed as anything
+/// else.
+pub const STAKE_TERMS_DOMAIN: &str = "staking_contract:stake_with_permit";
+
+/// Bytes hashed into the digest the owner signs to stake in the tier `lock_tier` of `pool` the
+/// tokens approved by the permit it signed with `permit_signature`.
+pub fn stake_terms_preimage(
+    pool: &Key,
+    permit_signature: &Signature,
+    lock_tier: u8,
+) -> Result<Vec<u8>, Error> {
+    let mut preimage = STAKE_TERMS_DOMAIN
+        .to_bytes()
+        .map_err(|_| Error::InvalidStakeTerms)?;
+    preimage.append(&mut pool.to_bytes().map_err(|_| Error::InvalidStakeTerms)?);
+    preimage.append(
+        &mut permit_signature
+            .to_bytes()
+            .map_err(|_| Error::InvalidStakeTerms)?,
+    );
+    preimage.append(&mut lock_tier.to_bytes().map_err(|_| Error::InvalidStakeTerms)?);
+    Ok(preimage)
+}
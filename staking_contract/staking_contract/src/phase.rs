@@ -0,0 +1,117 @@
+//! Lifecycle of a pool and the operations allowed at each of its phases.
+use crate::{reward::RewardMode, schedule::Schedule};
+
+/// Phase of a pool, following its [`Schedule`] unless it is paused.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PoolPhase {
+    /// Before `staking_starts`.
+    Pending = 0,
+    /// From `staking_starts` to `staking_ends`.
+    Staking = 1,
+    /// From `staking_ends` to `withdraw_starts`.
+    Locked = 2,
+    /// From `withdraw_starts` to `withdraw_ends`.
+    WithdrawWindow = 3,
+    /// From `withdraw_ends` on.
+    Closed = 4,
+    /// Paused by the pauser role, whatever the block time.
+    Paused = 5,
+}
+
+impl PoolPhase {
+    pub const SCHEDULED: [PoolPhase; 5] = [
+        PoolPhase::Pending,
+        PoolPhase::Staking,
+        PoolPhase::Locked,
+        PoolPhase::WithdrawWindow,
+        PoolPhase::Closed,
+    ];
+
+    pub const ALL: [PoolPhase; 6] = [
+        PoolPhase::Pending,
+        PoolPhase::Staking,
+        PoolPhase::Locked,
+        PoolPhase::WithdrawWindow,
+        PoolPhase::Closed,
+        PoolPhase::Paused,
+    ];
+
+    /// Phase at `now` of a pool following `schedule`.
+    pub fn at(now: u64, schedule: &Schedule, paused: bool) -> PoolPhase {
+        if paused {
+            PoolPhase::Paused
+        } else if now < schedule.staking_starts {
+            PoolPhase::Pending
+        } else if now < schedule.staking_ends {
+            PoolPhase::Staking
+        } else if now < schedule.withdraw_starts {
+            PoolPhase::Locked
+        } else if now < schedule.withdraw_ends {
+            PoolPhase::WithdrawWindow
+        } else {
+            PoolPhase::Closed
+        }
+    }
+}
+
+/// Operations guarded by [`crate::modifiers::in_phase`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Operation {
+    Stake,
+    Withdraw,
+    ClaimRewards,
+    AddReward,
+    Compound,
+    TransferStake,
+    SweepDust,
+    Pause,
+    Unpause,
+    EmergencyWithdraw,
+    Reschedule,
+    /// Changing the stake limits, the stake caps or the allowlist root.
+    ConfigureStaking,
+    SetReferralRate,
+    /// Approving and revoking operators, and setting payout destinations.
+    ManageOperators,
+    /// Granting, revoking and renouncing roles.
+    ManageRoles,
+    Migrate,
+}
+
+impl Operation {
+    /// Phases `self` is allowed in by a pool of the given reward mode.
+    ///
+    /// Pro-rata rewards are only known once staking ends, so those pools pay out from
+    /// `withdraw_starts` on, while streamed rewards can be paid out as soon as staking starts.
+    /// Operators and roles can be managed in every phase, so that a compromised account can be
+    /// revoked while the pool is paused, and an upgraded pool is migrated whatever its phase.
+    pub fn allowed_phases(self, reward_mode: RewardMode) -> &'static [PoolPhase] {
+        use PoolPhase::*;
+        match self {
+            Operation::Stake => &[Staking],
+            Operation::Withdraw | Operation::ClaimRewards if reward_mode.is_pro_rata() => {
+                &[WithdrawWindow, Closed]
+            }
+            Operation::Withdraw | Operation::ClaimRewards => {
+                &[Staking, Locked, WithdrawWindow, Closed]
+            }
+            Operation::AddReward => &[Pending, Staking, Locked],
+            Operation::Compound
+            | Operation::TransferStake
+            | Operation::Pause
+            | Operation::SetReferralRate => &PoolPhase::SCHEDULED,
+            Operation::SweepDust => &[Locked, WithdrawWindow, Closed],
+            Operation::Unpause | Operation::EmergencyWithdraw => &[Paused],
+            Operation::Reschedule => &[Pending, Staking, Locked, WithdrawWindow],
+            Operation::ConfigureStaking => &[Pending, Staking],
+            Operation::ManageOperators | Operation::ManageRoles | Operation::Migrate => {
+                &PoolPhase::ALL
+            }
+        }
+    }
+
+    pub fn is_allowed(self, phase: PoolPhase, reward_mode: RewardMode) -> bool {
+        self.allowed_phases(reward_mode).contains(&phase)
+    }
+}
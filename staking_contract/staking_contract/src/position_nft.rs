@@ -0,0 +1,50 @@
+//! CEP-78 NFTs minted for the positions of pools configured with a `position_nft` collection.
+//!
+//! The collection must use ordinal token identifiers and raw metadata, let the pool mint, and
+//! allow burning. Holders approve the pool on their token before redeeming it.
+use crate::{error::Error, lock::Position};
+use alloc::{format, string::String};
+use casper_contract::contract_api::runtime;
+use casper_types::{runtime_args, ContractPackageHash, Key, RuntimeArgs};
+
+/// Metadata of the NFT standing for `position`.
+pub fn metadata(position: &Position) -> String {
+    format!(
+        r#"{{"amount":"{}","lock_tier":"{}","unlock_time":"{}"}}"#,
+        position.amount, position.lock_tier, position.unlock_time
+    )
+}
+
+/// Parses the ordinal token identifier returned by the `mint` entry point of the collection.
+pub fn parse_token_id(token_id: &str) -> Result<u64, Error> {
+    token_id.parse().map_err(|_| Error::InvalidPositionNft)
+}
+
+/// Mints the NFT standing for `position` to `owner` and returns its token identifier.
+pub fn mint(
+    collection: ContractPackageHash,
+    owner: Key,
+    position: &Position,
+) -> Result<u64, Error> {
+    let args = runtime_args! {
+        "token_owner" => owner,
+        "token_meta_data" => metadata(position),
+    };
+    let (_, _, token_id): (String, Key, String) =
+        runtime::call_versioned_contract(collection, None, "mint", args);
+    parse_token_id(&token_id)
+}
+
+pub fn owner_of(collection: ContractPackageHash, token_id: u64) -> Key {
+    let args = runtime_args! {
+        "token_id" => token_id,
+    };
+    runtime::call_versioned_contract(collection, None, "owner_of", args)
+}
+
+pub fn burn(collection: ContractPackageHash, token_id: u64) {
+    let args = runtime_args! {
+        "token_id" => token_id,
+    };
+    runtime::call_versioned_contract::<()>(collection, None, "burn", args);
+}
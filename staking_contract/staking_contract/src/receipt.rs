@@ -0,0 +1,140 @@
+//! Receipt token minted for every unit staked in the pool.
+//!
+//! The receipt is a CEP-18 token hosted by the staking contract itself and stored with the layout of
+//! the `casper_erc20` crate: the `balances` and `allowances` dictionaries and the `total_supply`,
+//! `symbol` and `decimals` keys, next to the `name` of the pool. Those keys are not namespaced, so
+//! only a standalone pool installs the receipt; factory pools have none.
+use crate::{address::Address, error::Error};
+use alloc::string::String;
+use casper_contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_erc20::{
+    constants::{
+        ALLOWANCES_KEY_NAME, BALANCES_KEY_NAME, DECIMALS_KEY_NAME, SYMBOL_KEY_NAME,
+        TOTAL_SUPPLY_KEY_NAME,
+    },
+    ERC20,
+};
+use casper_types::U256;
+use contract_utils::set_key;
+
+/// Creates the storage of the receipt token, with no supply.
+pub fn init(symbol: String, decimals: u8) {
+    storage::new_dictionary(BALANCES_KEY_NAME).unwrap_or_revert();
+    storage::new_dictionary(ALLOWANCES_KEY_NAME).unwrap_or_revert();
+    set_key(TOTAL_SUPPLY_KEY_NAME, U256::zero());
+    set_key(SYMBOL_KEY_NAME, symbol);
+    set_key(DECIMALS_KEY_NAME, decimals);
+}
+
+/// Whether the pool issues receipts.
+pub fn enabled() -> bool {
+    runtime::has_key(TOTAL_SUPPLY_KEY_NAME)
+}
+
+/// Mints `amount` receipts to `owner`, if the pool issues any.
+pub fn mint(owner: Address, amount: U256) -> Result<(), Error> {
+    if !enabled() {
+        return Ok(());
+    }
+    ERC20::default()
+        .mint(to_erc20_address(owner)?, amount)
+        .map_err(from_erc20_error)
+}
+
+/// Burns `amount` receipts of `owner`, if the pool issues any.
+pub fn burn(owner: Address, amount: U256) -> Result<(), Error> {
+    if !enabled() {
+        return Ok(());
+    }
+    ERC20::default()
+        .burn(to_erc20_address(owner)?, amount)
+        .map_err(from_erc20_error)
+}
+
+/// Moves `amount` receipts from the immediate caller to `recipient`.
+pub fn transfer(recipient: Address, amount: U256) -> Result<(), Error> {
+    ERC20::default()
+        .transfer(to_erc20_address(recipient)?, amount)
+        .map_err(from_erc20_error)
+}
+
+/// Moves `amount` receipts from `owner` to `recipient` out of the allowance of the immediate caller.
+pub fn transfer_from(owner: Address, recipient: Address, amount: U256) -> Result<(), Error> {
+    ERC20::default()
+        .transfer_from(
+            to_erc20_address(owner)?,
+            to_erc20_address(recipient)?,
+            amount,
+        )
+        .map_err(from_erc20_error)
+}
+
+/// Allows `spender` to move up to `amount` receipts of the immediate caller.
+pub fn approve(spender: Address, amount: U256) -> Result<(), Error> {
+    if !enabled() {
+        return Err(Error::NoReceiptToken);
+    }
+    ERC20::default()
+        .approve(to_erc20_address(spender)?, amount)
+        .map_err(from_erc20_error)
+}
+
+pub fn balance_of(owner: Address) -> Result<U256, Error> {
+    if !enabled() {
+        return Ok(U256::zero());
+    }
+    Ok(ERC20::default().balance_of(to_erc20_address(owner)?))
+}
+
+pub fn allowance(owner: Address, spender: Address) -> Result<U256, Error> {
+    if !enabled() {
+        return Ok(U256::zero());
+    }
+    Ok(ERC20::default().allowance(to_erc20_address(owner)?, to_erc20_address(spender)?))
+}
+
+pub fn total_supply() -> U256 {
+    if !enabled() {
+        return U256::zero();
+    }
+    ERC20::default().total_supply()
+}
+
+pub fn symbol() -> Result<String, Error> {
+    if !enabled() {
+        return Err(Error::NoReceiptToken);
+    }
+    Ok(ERC20::default().symbol())
+}
+
+pub fn decimals() -> Result<u8, Error> {
+    if !enabled() {
+        return Err(Error::NoReceiptToken);
+    }
+    Ok(ERC20::default().decimals())
+}
+
+fn to_erc20_address(address: Address) -> Result<casper_erc20::Address, Error> {
+    match address {
+        Address::Account(account_hash) => Ok(casper_erc20::Address::from(account_hash)),
+        Address::ContractPackage(contract_package_hash) => {
+            Ok(casper_erc20::Address::from(contract_package_hash))
+        }
+        Address::ContractHash(_) => Err(Error::NeitherAccountHashNorNeitherContractPackageHash),
+    }
+}
+
+/// Maps the errors of the receipt token to the ones of the pool. Errors the receipt operations
+/// never return revert with their own code.
+fn from_erc20_error(error: casper_erc20::Error) -> Error {
+    match error {
+        casper_erc20::Error::InvalidContext => Error::ImmediateCallerAddressFail,
+        casper_erc20::Error::InsufficientBalance => Error::InsufficientReceiptBalance,
+        casper_erc20::Error::InsufficientAllowance => Error::InsufficientReceiptAllowance,
+        casper_erc20::Error::Overflow => Error::Overflow,
+        error => runtime::revert(error),
+    }
+}
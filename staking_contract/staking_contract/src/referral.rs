@@ -0,0 +1,18 @@
+//! Referral program: referrers get a share of the rewards of the stakers they brought in.
+use crate::{error::Error, safe_math};
+use casper_types::U256;
+
+/// Referral rates are expressed in basis points of the rewards of the referred staker.
+pub const REFERRAL_BASIS_POINTS: u64 = 10_000;
+
+/// Share of `reward` credited to the referrer at `referral_rate`, taken out of the reward.
+pub fn referral_share(reward: U256, referral_rate: u64) -> Result<U256, Error> {
+    if referral_rate > REFERRAL_BASIS_POINTS {
+        return Err(Error::InvalidReferralRate);
+    }
+    safe_math::mul_div(
+        reward,
+        U256::from(referral_rate),
+        U256::from(REFERRAL_BASIS_POINTS),
+    )
+}
@@ -0,0 +1,140 @@
+//! Reward calculations shared by the staking entry points.
+use crate::{
+    error::Error,
+    safe_math::{self, SafeMath},
+};
+use casper_types::U256;
+use core::convert::TryFrom;
+
+/// Scale of the `reward_per_token_stored` accumulator.
+pub const REWARD_PRECISION: u64 = 1_000_000_000_000_000_000;
+/// Block time is expressed in milliseconds while reward rates are expressed per second.
+pub const MILLISECONDS_PER_SECOND: u64 = 1_000;
+
+/// How the rewards of a pool are distributed among its stakers.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RewardMode {
+    /// The reward balance is split pro-rata between stakers when they withdraw.
+    ProRata = 0,
+    /// Rewards accrue per second of block time to everyone staked at that time.
+    Streaming = 1,
+    /// Same split as [`RewardMode::ProRata`], computed with [`REWARD_PRECISION`] and carrying the
+    /// fraction of a token each staker is owed over to its next withdrawal.
+    FixedPoint = 2,
+    /// Stakes are shares of a vault the streamed rewards are compounded into.
+    Vault = 3,
+}
+
+impl RewardMode {
+    /// Whether rewards are split between stakers when they withdraw, as in `Staking.sol`.
+    pub fn is_pro_rata(self) -> bool {
+        matches!(self, RewardMode::ProRata | RewardMode::FixedPoint)
+    }
+}
+
+impl TryFrom<u8> for RewardMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RewardMode::ProRata),
+            1 => Ok(RewardMode::Streaming),
+            2 => Ok(RewardMode::FixedPoint),
+            3 => Ok(RewardMode::Vault),
+            _ => Err(Error::InvalidRewardMode),
+        }
+    }
+}
+
+/// Returns the value of the reward-per-token accumulator at `now`.
+///
+/// `reward_rate` is the amount of reward tokens streamed per second, scaled by
+/// [`REWARD_PRECISION`], and shared by `total_staked`. Nothing accrues while nothing is staked.
+pub fn reward_per_token(
+    reward_per_token_stored: U256,
+    reward_rate: U256,
+    last_update_time: u64,
+    now: u64,
+    total_staked: U256,
+) -> Result<U256, Error> {
+    if total_staked.is_zero() || now <= last_update_time {
+        return Ok(reward_per_token_stored);
+    }
+    let elapsed = U256::from(now.safe_sub(last_update_time)?);
+    reward_per_token_stored.safe_add(safe_math::mul_div(
+        reward_rate,
+        elapsed,
+        U256::from(MILLISECONDS_PER_SECOND).safe_mul(total_staked)?,
+    )?)
+}
+
+/// Returns the rewards streamed at `reward_rate`, scaled like in [`reward_per_token`], between
+/// `last_update_time` and `now`.
+pub fn streamed_reward(reward_rate: U256, last_update_time: u64, now: u64) -> Result<U256, Error> {
+    if now <= last_update_time {
+        return Ok(U256::zero());
+    }
+    safe_math::mul_div(
+        reward_rate,
+        U256::from(now.safe_sub(last_update_time)?),
+        U256::from(MILLISECONDS_PER_SECOND).safe_mul(U256::from(REWARD_PRECISION))?,
+    )
+}
+
+/// Returns the reward debt of a staker holding `amount` at the given accumulator value.
+pub fn reward_debt(amount: U256, reward_per_token: U256) -> Result<U256, Error> {
+    safe_math::mul_div(amount, reward_per_token, U256::from(REWARD_PRECISION))
+}
+
+/// Returns the rewards accrued by `amount` since its reward debt was last synced.
+pub fn earned(amount: U256, reward_per_token: U256, debt: U256) -> Result<U256, Error> {
+    reward_debt(amount, reward_per_token)?.safe_sub(debt)
+}
+
+/// Returns the scaled per-second reward rate streaming `reward` plus whatever is left of the
+/// current stream over the `remaining` milliseconds of the reward period.
+pub fn reward_rate(reward: U256, current_rate: U256, remaining: u64) -> Result<U256, Error> {
+    if remaining == 0 {
+        return Err(Error::BadTiming);
+    }
+    let remaining = U256::from(remaining);
+    let scaled_reward = reward
+        .safe_mul(U256::from(REWARD_PRECISION))?
+        .safe_mul(U256::from(MILLISECONDS_PER_SECOND))?;
+    scaled_reward
+        .safe_add(current_rate.safe_mul(remaining)?)?
+        .safe_div(remaining)
+}
+
+/// Reward paid on `amount` withdrawn at `now`, between `withdraw_starts` and `withdraw_ends`, by the
+/// pro-rata reward mode.
+///
+/// Port of `Staking.sol::_withdrawEarly`:
+/// `earlyWithdrawReward * (now - stakingEnds) * amount / ((withdrawEnds - stakingEnds) * stakedTotal)`,
+/// failing wherever the `SafeMath` operations of the original revert.
+pub fn early_withdraw_reward(
+    early_withdraw_reward: U256,
+    amount: U256,
+    staked_total: U256,
+    now: u64,
+    staking_ends: u64,
+    withdraw_ends: u64,
+) -> Result<U256, Error> {
+    let denom = U256::from(withdraw_ends.safe_sub(staking_ends)?).safe_mul(staked_total)?;
+    U256::from(now.safe_sub(staking_ends)?)
+        .safe_mul(early_withdraw_reward)?
+        .safe_mul(amount)?
+        .safe_div(denom)
+}
+
+/// Reward paid on `amount` withdrawn from `withdraw_ends` on by the pro-rata reward mode.
+///
+/// Port of `Staking.sol::_withdrawAfterClose`: `rewardBalance * amount / stakedBalance`.
+pub fn after_close_reward(
+    reward_balance: U256,
+    amount: U256,
+    staked_balance: U256,
+) -> Result<U256, Error> {
+    safe_math::mul_div(reward_balance, amount, staked_balance)
+}
@@ -0,0 +1,31 @@
+//! Roles administering a staking pool.
+use crate::error::Error;
+use core::convert::TryFrom;
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    /// Grants and revokes every role, including its own.
+    Owner = 0,
+    /// Funds the pool with `add_reward`.
+    RewardManager = 1,
+    /// Pauses and unpauses the pool.
+    Pauser = 2,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Owner, Role::RewardManager, Role::Pauser];
+}
+
+impl TryFrom<u8> for Role {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Role::Owner),
+            1 => Ok(Role::RewardManager),
+            2 => Ok(Role::Pauser),
+            _ => Err(Error::InvalidRole),
+        }
+    }
+}
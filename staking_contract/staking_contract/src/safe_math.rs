@@ -0,0 +1,46 @@
+//! Checked arithmetic for the staking accounting.
+//!
+//! Every operation fails with [`Error::Overflow`], [`Error::Underflow`] or
+//! [`Error::DivisionByZero`] instead of panicking, so that a bad amount reverts the call with a
+//! meaningful error code.
+use crate::error::Error;
+use casper_types::U256;
+
+/// Checked arithmetic on the integer types of the pool state.
+pub trait SafeMath: Sized {
+    fn safe_add(self, rhs: Self) -> Result<Self, Error>;
+    fn safe_sub(self, rhs: Self) -> Result<Self, Error>;
+    fn safe_mul(self, rhs: Self) -> Result<Self, Error>;
+    fn safe_div(self, rhs: Self) -> Result<Self, Error>;
+}
+
+macro_rules! impl_safe_math {
+    ($($t:ty),*) => {
+        $(
+            impl SafeMath for $t {
+                fn safe_add(self, rhs: Self) -> Result<Self, Error> {
+                    self.checked_add(rhs).ok_or(Error::Overflow)
+                }
+
+                fn safe_sub(self, rhs: Self) -> Result<Self, Error> {
+                    self.checked_sub(rhs).ok_or(Error::Underflow)
+                }
+
+                fn safe_mul(self, rhs: Self) -> Result<Self, Error> {
+                    self.checked_mul(rhs).ok_or(Error::Overflow)
+                }
+
+                fn safe_div(self, rhs: Self) -> Result<Self, Error> {
+                    self.checked_div(rhs).ok_or(Error::DivisionByZero)
+                }
+            }
+        )*
+    };
+}
+
+impl_safe_math!(u32, u64, U256);
+
+/// Returns `a * b / c`.
+pub fn mul_div(a: U256, b: U256, c: U256) -> Result<U256, Error> {
+    a.safe_mul(b)?.safe_div(c)
+}
@@ -0,0 +1,77 @@
+//! Staking and withdrawal windows of a pool.
+use crate::error::Error;
+
+/// The staking window runs from `staking_starts` to `staking_ends` and the withdrawal window from
+/// `withdraw_starts` to `withdraw_ends`, all in milliseconds of block time.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Schedule {
+    pub staking_starts: u64,
+    pub staking_ends: u64,
+    pub withdraw_starts: u64,
+    pub withdraw_ends: u64,
+}
+
+impl Schedule {
+    /// Port of the checks of the `Staking.sol` constructor: a `staking_starts` in the past is moved
+    /// to `now`, and the windows must be non-empty, with withdrawals starting once staking ends.
+    pub fn validate(self, now: u64) -> Result<Schedule, Error> {
+        Schedule {
+            staking_starts: self.staking_starts.max(now),
+            ..self
+        }
+        .ordered()
+    }
+
+    /// Validates `next` as a replacement of `self` at `now`. A window can only change before it
+    /// starts, and only to be extended: start no later and end no earlier.
+    pub fn reschedule(&self, next: Schedule, now: u64) -> Result<Schedule, Error> {
+        // A start left unchanged is kept even once it has passed.
+        let next = if next.staking_starts == self.staking_starts {
+            next.ordered()?
+        } else {
+            next.validate(now)?
+        };
+        extend_window(
+            (self.staking_starts, self.staking_ends),
+            (next.staking_starts, next.staking_ends),
+            now,
+        )?;
+        extend_window(
+            (self.withdraw_starts, self.withdraw_ends),
+            (next.withdraw_starts, next.withdraw_ends),
+            now,
+        )?;
+        Ok(next)
+    }
+
+    /// Length of the staking window, over which streamed rewards are spread.
+    pub fn staking_duration(&self) -> u64 {
+        self.staking_ends.saturating_sub(self.staking_starts)
+    }
+
+    fn ordered(self) -> Result<Schedule, Error> {
+        if self.staking_ends <= self.staking_starts {
+            return Err(Error::InvalidStakingEnds);
+        }
+        if self.withdraw_starts < self.staking_ends {
+            return Err(Error::InvalidWithdrawStarts);
+        }
+        if self.withdraw_ends <= self.withdraw_starts {
+            return Err(Error::InvalidWithdrawEnds);
+        }
+        Ok(self)
+    }
+}
+
+fn extend_window(current: (u64, u64), next: (u64, u64), now: u64) -> Result<(), Error> {
+    if current == next {
+        return Ok(());
+    }
+    if now >= current.0 {
+        return Err(Error::WindowStarted);
+    }
+    if next.0 > current.0 || next.1 < current.1 {
+        return Err(Error::ScheduleShortened);
+    }
+    Ok(())
+}
@@ -3,15 +3,31 @@ use crate::error::Error;
 use crate::modifiers;
 use crate::{
     address::Address,
-    data::{self, StakedTokens},
+    allowlist::{AllowlistProof, Hash},
+    data::{self, Operators, PositionNfts, Referrals, StakeCaps, StakedTokens, StakerRewards},
     event::StakingContractEvent,
+    limit::StakeLimits,
+    lock::{LockTier, Position},
+    penalty::{self, Penalty, PenaltyMode},
+    permit,
+    phase::{Operation, PoolPhase},
+    position_nft, receipt, referral,
+    reward::{self, RewardMode},
+    role::Role,
+    safe_math::{self, SafeMath},
+    schedule::Schedule,
 };
-use alloc::string::String;
+use alloc::{format, string::String, vec::Vec};
 use casper_contract::{contract_api::runtime, unwrap_or_revert::UnwrapOrRevert};
-use casper_types::{runtime_args, BlockTime, ContractPackageHash, Key, RuntimeArgs, U256};
-use contract_utils::{ContractContext, ContractStorage};
+use casper_types::{
+    crypto, runtime_args, BlockTime, ContractPackageHash, Key, PublicKey, RuntimeArgs, Signature,
+    U256,
+};
+use contract_utils::{ContractContext, ContractStorage, RoleControl};
 
-pub trait CEP20STK<Storage: ContractStorage>: ContractContext<Storage> {
+pub trait CEP20STK<Storage: ContractStorage>:
+    ContractContext<Storage> + RoleControl<Storage>
+{
     #[allow(clippy::too_many_arguments)]
     fn init(
         &mut self,
@@ -22,15 +38,69 @@ pub trait CEP20STK<Storage: ContractStorage>: ContractContext<Storage> {
         withdraw_starts: u64,
         withdraw_ends: u64,
         staking_total: U256,
+        erc20_contract_package_hash: Key,
+        reward_token_package_hash: Key,
+        reward_mode: RewardMode,
+        lock_tiers: Vec<LockTier>,
+        penalty: Penalty,
+        position_nft: Option<Key>,
+        allowlist_root: Option<Hash>,
     ) {
+        // Forfeited staking tokens can only be paid out as rewards when both are the same token.
+        if penalty.rate > 0
+            && penalty.mode == PenaltyMode::Redistribute
+            && erc20_contract_package_hash != reward_token_package_hash
+        {
+            runtime::revert(Error::InvalidPenalty);
+        }
+        // Vault rewards are compounded into the stakes, so they must be paid in the staking token.
+        if reward_mode == RewardMode::Vault
+            && erc20_contract_package_hash != reward_token_package_hash
+        {
+            runtime::revert(Error::InvalidRewardMode);
+        }
+        if staking_total.is_zero() {
+            runtime::revert(Error::InvalidStakingTotal);
+        }
+        let schedule = Schedule {
+            staking_starts,
+            staking_ends,
+            withdraw_starts,
+            withdraw_ends,
+        }
+        .validate(u64::from(runtime::get_blocktime()))
+        .unwrap_or_revert();
         data::set_name(name);
         data::set_address(address);
-        data::set_staking_starts(staking_starts);
-        data::set_staking_ends(staking_ends);
-        data::set_withdraw_starts(withdraw_starts);
-        data::set_withdraw_ends(withdraw_ends);
+        self.set_schedule(schedule);
         data::set_staking_total(staking_total);
+        data::set_erc20_contract_package_hash(erc20_contract_package_hash);
+        data::set_reward_token_package_hash(reward_token_package_hash);
+        data::set_reward_mode(reward_mode);
+        data::set_lock_tiers(lock_tiers);
+        data::set_penalty(penalty);
+        data::set_position_nft(position_nft);
+        data::set_allowlist_root(allowlist_root);
+        data::set_schema_version(data::CURRENT_SCHEMA_VERSION);
         StakedTokens::init();
+        StakerRewards::init();
+        PositionNfts::init();
+        Operators::init();
+        StakeCaps::init();
+        Referrals::init();
+    }
+
+    /// Sets up the role registry and grants every role to `owner`.
+    fn init_roles(&mut self, owner: Key) {
+        RoleControl::init(self);
+        for role in Role::ALL {
+            self.grant_role_without_checked(role as u8, owner);
+            self.emit(StakingContractEvent::RoleGranted {
+                role,
+                account: owner,
+                sender: owner,
+            });
+        }
     }
 
     fn name(&self) -> String {
@@ -57,6 +127,53 @@ pub trait CEP20STK<Storage: ContractStorage>: ContractContext<Storage> {
         data::withdraw_ends()
     }
 
+    fn schedule(&self) -> Schedule {
+        Schedule {
+            staking_starts: self.staking_starts(),
+            staking_ends: self.staking_ends(),
+            withdraw_starts: self.withdraw_starts(),
+            withdraw_ends: self.withdraw_ends(),
+        }
+    }
+
+    fn set_schedule(&self, schedule: Schedule) {
+        data::set_staking_starts(schedule.staking_starts);
+        data::set_staking_ends(schedule.staking_ends);
+        data::set_withdraw_starts(schedule.withdraw_starts);
+        data::set_withdraw_ends(schedule.withdraw_ends);
+    }
+
+    /// Replaces the schedule of the pool with `schedule`, which may only extend the windows that
+    /// have not started yet. Rewards already streaming over the staking window are spread over its
+    /// new length.
+    fn reschedule(&mut self, schedule: Schedule) -> Result<(), Error> {
+        modifiers::in_phase(Operation::Reschedule)?;
+        self.only_role(Role::Owner)?;
+        let current = self.schedule();
+        let schedule = current.reschedule(schedule, u64::from(runtime::get_blocktime()))?;
+        if (schedule.staking_starts, schedule.staking_ends)
+            != (current.staking_starts, current.staking_ends)
+        {
+            // Nothing has streamed yet, as the staking window has not started.
+            if !self.reward_rate().is_zero() {
+                data::set_reward_rate(safe_math::mul_div(
+                    self.reward_rate(),
+                    U256::from(current.staking_duration()),
+                    U256::from(schedule.staking_duration()),
+                )?);
+            }
+            data::set_last_update_time(schedule.staking_starts);
+        }
+        self.set_schedule(schedule);
+        self.emit(StakingContractEvent::ScheduleChanged {
+            staking_starts: schedule.staking_starts,
+            staking_ends: schedule.staking_ends,
+            withdraw_starts: schedule.withdraw_starts,
+            withdraw_ends: schedule.withdraw_ends,
+        });
+        Ok(())
+    }
+
     fn staking_total(&self) -> U256 {
         data::staking_total()
     }
@@ -65,6 +182,71 @@ pub trait CEP20STK<Storage: ContractStorage>: ContractContext<Storage> {
         data::set_staking_total(staking_total)
     }
 
+    fn stake_limits(&self) -> StakeLimits {
+        data::stake_limits()
+    }
+
+    /// Sets the smallest amount a stake may request and the most an address may have staked, if
+    /// limited. Stakes made before are kept even when they exceed the new maximum.
+    fn set_stake_limits(&mut self, stake_limits: StakeLimits) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ConfigureStaking)?;
+        self.only_role(Role::Owner)?;
+        data::set_stake_limits(stake_limits);
+        self.emit(StakingContractEvent::StakeLimitsChanged {
+            min_stake: stake_limits.min_stake,
+            max_stake_per_address: stake_limits.max_stake_per_address,
+        });
+        Ok(())
+    }
+
+    /// Cap an admin gave `staker`, overriding the maximum stake per address of the pool.
+    fn stake_cap(&self, staker: Key) -> Option<U256> {
+        StakeCaps::instance().cap(&staker)
+    }
+
+    /// Gives `staker` its own cap, or with `None` subjects it to the maximum stake per address of
+    /// the pool again.
+    fn set_stake_cap(&mut self, staker: Key, cap: Option<U256>) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ConfigureStaking)?;
+        self.only_role(Role::Owner)?;
+        StakeCaps::instance().set_cap(&staker, cap);
+        self.emit(StakingContractEvent::StakeCapChanged {
+            staker_address: staker,
+            cap,
+        });
+        Ok(())
+    }
+
+    /// Root of the allowlist of the pool, if only allowlisted addresses may stake in it.
+    fn allowlist_root(&self) -> Option<Hash> {
+        data::allowlist_root()
+    }
+
+    /// Restricts the pool to the allowlist of `root`, or with `None` opens it to everyone. Stakes
+    /// made before are kept.
+    fn set_allowlist_root(&mut self, root: Option<Hash>) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ConfigureStaking)?;
+        self.only_role(Role::Owner)?;
+        data::set_allowlist_root(root);
+        self.emit(StakingContractEvent::AllowlistRootChanged { root });
+        Ok(())
+    }
+
+    /// Staking tokens `staker` has in the pool, converting vault shares at their current price.
+    fn staked_tokens(&self, staker: &Key) -> Result<U256, Error> {
+        let units = StakedTokens::instance()
+            .get_amount_staked_by_address(staker)
+            .unwrap_or_default();
+        if self.reward_mode() != RewardMode::Vault || units.is_zero() {
+            return Ok(units);
+        }
+        safe_math::mul_div(
+            units,
+            self.price_per_share()?,
+            U256::from(reward::REWARD_PRECISION),
+        )
+    }
+
     fn reward_balance(&self) -> U256 {
         data::reward_balance()
     }
@@ -105,47 +287,310 @@ pub trait CEP20STK<Storage: ContractStorage>: ContractContext<Storage> {
         data::set_staked_total(staked_total)
     }
 
+    /// Package hash of the token the rewards are funded and paid in.
+    fn reward_token_package_hash(&self) -> Key {
+        data::reward_token_package_hash()
+    }
+
+    fn weighted_balance(&self) -> U256 {
+        data::weighted_balance()
+    }
+
+    fn set_weighted_balance(&self, weighted_balance: U256) {
+        data::set_weighted_balance(weighted_balance)
+    }
+
+    /// Lock tiers stakers choose from, indexed by their position in the list.
+    fn lock_tiers(&self) -> Vec<LockTier> {
+        data::lock_tiers()
+    }
+
+    fn lock_tier(&self, lock_tier: u8) -> Result<LockTier, Error> {
+        self.lock_tiers()
+            .get(lock_tier as usize)
+            .copied()
+            .ok_or(Error::InvalidLockTier)
+    }
+
+    /// Penalty charged on withdrawals before `withdraw_ends`.
+    fn penalty(&self) -> Penalty {
+        data::penalty()
+    }
+
+    fn reward_mode(&self) -> RewardMode {
+        data::reward_mode()
+    }
+
+    fn reward_rate(&self) -> U256 {
+        data::reward_rate()
+    }
+
+    fn reward_per_token_stored(&self) -> U256 {
+        data::reward_per_token_stored()
+    }
+
     fn amount_staked(&self, staker: Key) -> Result<U256, Error> {
         StakedTokens::instance()
             .get_amount_staked_by_address(&staker)
             .ok_or(Error::NotAStaker)
     }
 
+    /// Positions of `staker` still holding tokens, oldest first.
+    fn positions(&self, staker: Key) -> Vec<Position> {
+        StakedTokens::instance().positions(&staker)
+    }
+
+    /// Position `position_id` of `staker`, including fully withdrawn ones.
+    fn position(&self, staker: Key, position_id: u64) -> Result<Position, Error> {
+        StakedTokens::instance()
+            .position(&staker, position_id)
+            .ok_or(Error::UnknownPosition)
+    }
+
+    fn position_count(&self, staker: Key) -> u64 {
+        StakedTokens::instance().position_count(&staker)
+    }
+
+    /// Stakes `amount` out of the caller's allowance. A caller staking for the first time may name
+    /// the `referrer` it was brought in by. Pools restricted to an allowlist only take stakes made
+    /// with [`CEP20STK::stake_with_proof`].
     fn stake(
         &mut self,
         amount: U256,
         staking_contract_package_hash: String,
+        lock_tier: u8,
+        referrer: Option<Key>,
     ) -> Result<U256, Error> {
-        modifiers::positive(amount)?;
-        modifiers::after(self.staking_starts())?;
-        modifiers::before(self.staking_ends())?;
-        // check for has enough tokens
+        let staker_address = detail::get_immediate_caller_address()
+            .unwrap_or_revert_with(Error::ImmediateCallerAddressFail);
+        self.stake_from(
+            staker_address,
+            staker_address,
+            amount,
+            staking_contract_package_hash,
+            lock_tier,
+            None,
+            referrer,
+        )
+    }
 
-        let token_address = self.address();
+    /// Records `referrer` as the referrer of `staker_address`, which must be staking its first
+    /// position. Vault pools take no referrers, as their rewards are compounded rather than paid.
+    fn refer(&mut self, staker_address: Address, referrer: Key) -> Result<(), Error> {
+        if self.reward_mode() == RewardMode::Vault {
+            return Err(Error::ReferralsUnsupported);
+        }
+        let staker = Key::from(staker_address);
+        if referrer == staker
+            || Address::try_from(referrer).is_err()
+            || self.position_count(staker) > 0
+        {
+            return Err(Error::InvalidReferrer);
+        }
+        Referrals::instance().set_referrer(&staker, referrer);
+        Ok(())
+    }
 
-        let stakers_dict = StakedTokens::instance();
+    /// Referrer `staker` named when it first staked, if any.
+    fn referrer(&self, staker: Key) -> Option<Key> {
+        Referrals::instance().referrer(&staker)
+    }
+
+    /// Rewards credited to `referrer` so far.
+    fn referral_rewards(&self, referrer: Key) -> U256 {
+        Referrals::instance().referral_rewards(&referrer)
+    }
+
+    fn referral_rate(&self) -> u64 {
+        data::referral_rate()
+    }
+
+    /// Sets the share of the rewards of referred stakers credited to their referrer, in basis
+    /// points. It applies to every reward paid from then on.
+    fn set_referral_rate(&mut self, referral_rate: u64) -> Result<(), Error> {
+        modifiers::in_phase(Operation::SetReferralRate)?;
+        self.only_role(Role::Owner)?;
+        if referral_rate > referral::REFERRAL_BASIS_POINTS {
+            return Err(Error::InvalidReferralRate);
+        }
+        data::set_referral_rate(referral_rate);
+        self.emit(StakingContractEvent::ReferralRateChanged { referral_rate });
+        Ok(())
+    }
+
+    /// Pays the referrer of `staker_address` its share of `reward` and returns what is left of the
+    /// reward for the staker.
+    fn credit_referrer(&mut self, staker_address: Address, reward: U256) -> Result<U256, Error> {
+        let referrals = Referrals::instance();
+        let referrer = match referrals.referrer(&Key::from(staker_address)) {
+            Some(referrer) => referrer,
+            None => return Ok(reward),
+        };
+        let share = referral::referral_share(reward, self.referral_rate())?;
+        if share.is_zero() {
+            return Ok(reward);
+        }
+        let recipient = Address::try_from(referrer).map_err(|_| Error::InvalidReferrer)?;
+        referrals.set_referral_rewards(
+            &referrer,
+            referrals.referral_rewards(&referrer).safe_add(share)?,
+        );
+        data::set_distributed_rewards(data::distributed_rewards().safe_add(share)?);
+        self.pay_reward(recipient, share)?;
+        self.emit(StakingContractEvent::ReferralCredited {
+            token_address: self.address(),
+            referrer,
+            staker_address,
+            reward: share,
+        });
+        reward.safe_sub(share)
+    }
+
+    /// Stakes `amount` out of the caller's allowance and credits the stake to `beneficiary`, which
+    /// must have approved the caller as its operator. The caller, an account or a contract package,
+    /// gets any refund. Fails with `NotAllowlisted` in pools restricted to an allowlist.
+    fn stake_for(
+        &mut self,
+        beneficiary: Address,
+        amount: U256,
+        staking_contract_package_hash: String,
+        lock_tier: u8,
+    ) -> Result<U256, Error> {
+        // Positions are only opened with the consent of their owner, so that nobody can fill the
+        // positions of someone else with dust.
+        self.only_staker_or_operator(beneficiary)?;
         let staker_address = detail::get_immediate_caller_address()
             .unwrap_or_revert_with(Error::ImmediateCallerAddressFail);
+        self.stake_from(
+            staker_address,
+            beneficiary,
+            amount,
+            staking_contract_package_hash,
+            lock_tier,
+            None,
+            None,
+        )
+    }
 
-        let mut remaining_token = amount;
+    /// Stakes `amount` out of the caller's allowance in a pool restricted to an allowlist. `proof`
+    /// shows the allowlist grants the caller `allocation`, the most it may have staked. A caller
+    /// staking for the first time may name the `referrer` it was brought in by.
+    fn stake_with_proof(
+        &mut self,
+        amount: U256,
+        staking_contract_package_hash: String,
+        lock_tier: u8,
+        allocation: U256,
+        proof: Vec<Hash>,
+        referrer: Option<Key>,
+    ) -> Result<U256, Error> {
+        let staker_address = detail::get_immediate_caller_address()
+            .unwrap_or_revert_with(Error::ImmediateCallerAddressFail);
+        self.stake_from(
+            staker_address,
+            staker_address,
+            amount,
+            staking_contract_package_hash,
+            lock_tier,
+            Some(AllowlistProof { allocation, proof }),
+            referrer,
+        )
+    }
 
-        if let Some(diff) = self.staking_total().checked_sub(remaining_token) {
-            if remaining_token > diff {
-                remaining_token = diff;
-            }
-        }
+    /// Approves the pool with the permit `signature` made by the owner of `public_key` over the
+    /// staking token, then stakes `amount` out of that allowance for the owner. `terms_signature`
+    /// is the owner's signature of the stake terms, which cover the lock tier, so anyone can submit
+    /// them. Fails with `NotAllowlisted` in pools restricted to an allowlist.
+    #[allow(clippy::too_many_arguments)]
+    fn stake_with_permit(
+        &mut self,
+        public_key: PublicKey,
+        amount: U256,
+        deadline: u64,
+        signature: Signature,
+        terms_signature: Signature,
+        staking_contract_package_hash: String,
+        lock_tier: u8,
+    ) -> Result<U256, Error> {
+        let owner = Address::from(public_key.to_account_hash());
+        let pool = Key::from(self.stacking_contract_address());
+        let digest = runtime::blake2b(permit::stake_terms_preimage(&pool, &signature, lock_tier)?);
+        crypto::verify(digest, &terms_signature, &public_key)
+            .map_err(|_| Error::InvalidStakeTerms)?;
+        self.permit(
+            self.erc20_metadata(),
+            public_key,
+            self.stacking_contract_address(),
+            amount,
+            deadline,
+            signature,
+        );
+        self.stake_from(
+            owner,
+            owner,
+            amount,
+            staking_contract_package_hash,
+            lock_tier,
+            None,
+            None,
+        )
+    }
+
+    /// Stakes `amount` out of the allowance `staker_address` gave to the pool and credits the stake
+    /// to `beneficiary`. `staker_address` gets any refund. Pools restricted to an allowlist require
+    /// `allowlist_proof` to prove the allocation of `beneficiary`, and the first position of
+    /// `beneficiary` may name its `referrer`.
+    #[allow(clippy::too_many_arguments)]
+    fn stake_from(
+        &mut self,
+        staker_address: Address,
+        beneficiary: Address,
+        amount: U256,
+        staking_contract_package_hash: String,
+        lock_tier: u8,
+        allowlist_proof: Option<AllowlistProof>,
+        referrer: Option<Key>,
+    ) -> Result<U256, Error> {
+        modifiers::in_phase(Operation::Stake)?;
+        let stake_limits = self.stake_limits();
+        stake_limits.check_amount(amount)?;
+        // check for has enough tokens
+
+        let token_address = self.address();
+
+        let stakers_dict = StakedTokens::instance();
+
+        // Only the room left under the cap is staked, as in `Staking.sol`.
+        let remaining_token = amount.min(self.staking_total().saturating_sub(self.staked_total()));
 
         if remaining_token <= U256::from(0u64) {
             return Err(Error::NotRequiredStake);
         }
 
-        if (remaining_token + self.staked_total()) > self.staking_total() {
+        if remaining_token.safe_add(self.staked_total())? > self.staking_total() {
             return Err(Error::NotRequiredStake);
         }
 
+        let staker = Key::from(beneficiary);
+        let staked_tokens = self.staked_tokens(&staker)?;
+        stake_limits.check_cap(remaining_token, staked_tokens, self.stake_cap(staker))?;
+        if let Some(root) = self.allowlist_root() {
+            let allowlist_proof = allowlist_proof.ok_or(Error::NotAllowlisted)?;
+            allowlist_proof.verify(&root, &staker, |bytes| runtime::blake2b(bytes))?;
+            if staked_tokens.safe_add(remaining_token)? > allowlist_proof.allocation {
+                return Err(Error::AllocationExceeded);
+            }
+        }
+        if let Some(referrer) = referrer {
+            self.refer(beneficiary, referrer)?;
+        }
+
         let staking_contract_package_hash =
             ContractPackageHash::from_formatted_str(staking_contract_package_hash.as_str())
                 .map_err(|_| Error::NotStakingContractPackageHash)?;
+        let tier = self.lock_tier(lock_tier)?;
+        let timestamp = u64::from(runtime::get_blocktime());
+        let unlock_time = timestamp.safe_add(tier.duration)?;
 
         self.pay_to(
             staker_address,
@@ -158,97 +603,661 @@ pub trait CEP20STK<Storage: ContractStorage>: ContractContext<Storage> {
             staker_address,
             requested_amount: amount,
             staked_amount: remaining_token,
+            position_id: stakers_dict.position_count(&staker),
+            lock_tier,
+            unlock_time,
+            beneficiary,
         });
 
+        // The rest of the amount was never taken from the staker, so the refund is only recorded.
         if remaining_token < amount {
-            let refund = amount - remaining_token;
-            self.pay_direct(staker_address, refund)
-                .unwrap_or_revert_with(Error::NegativeAmount);
+            self.emit(StakingContractEvent::Refunded {
+                token_address: self.address(),
+                staker_address,
+                amount: amount.safe_sub(remaining_token)?,
+            });
+        }
+
+        if self.reward_mode() == RewardMode::Streaming {
+            self.checkpoint(&staker)?;
+        }
+        // Vault stakes are recorded in shares of the vault.
+        let units = if self.reward_mode() == RewardMode::Vault {
+            self.issue_shares(remaining_token)?
+        } else {
+            remaining_token
+        };
+
+        let weight = tier.weight(units)?;
+        self.set_staked_total(self.staked_total().safe_add(remaining_token)?);
+        data::set_outstanding_stake(data::outstanding_stake().safe_add(units)?);
+        data::set_weighted_total(data::weighted_total().safe_add(weight)?);
+        self.set_staked_balance(self.staked_balance().safe_add(units)?);
+        self.set_weighted_balance(self.weighted_balance().safe_add(weight)?);
+        let position =
+            stakers_dict.add_stake(&staker, units, timestamp, lock_tier, unlock_time, weight)?;
+        receipt::mint(beneficiary, units)?;
+        if let Some(collection) = self.position_nft_metadata() {
+            let token_id = position_nft::mint(collection, staker, &position)?;
+            PositionNfts::instance().set_token_id(&staker, position.id, token_id);
         }
 
-        self.set_staking_total(self.staking_total() + remaining_token);
-        self.set_staked_balance(self.staked_balance() + remaining_token);
-        stakers_dict.add_stake(&Key::from(staker_address), &remaining_token);
+        if self.reward_mode() == RewardMode::Streaming {
+            self.sync_reward_debt(&staker)?;
+        }
         Ok(amount)
     }
 
+    /// Withdraws `amount` from the unlocked positions of the caller, oldest first.
     fn withdraw(&mut self, amount: U256) -> Result<U256, Error> {
-        modifiers::positive(amount)?;
-        modifiers::after(self.staking_starts())?;
+        modifiers::without_position_nft()?;
+        let caller_address = detail::get_immediate_caller_address()?;
+        self.withdraw_from(caller_address, None, amount)
+    }
+
+    /// Withdraws `amount` from the unlocked positions of `staker`, oldest first, on behalf of a
+    /// staker that approved the caller as its operator.
+    fn withdraw_for(&mut self, staker: Address, amount: U256) -> Result<U256, Error> {
+        modifiers::without_position_nft()?;
+        self.only_staker_or_operator(staker)?;
+        self.withdraw_from(staker, None, amount)
+    }
+
+    /// Withdraws `amount` from the position `position_id` of the caller.
+    fn withdraw_position(&mut self, position_id: u64, amount: U256) -> Result<U256, Error> {
+        modifiers::without_position_nft()?;
+        let caller_address = detail::get_immediate_caller_address()?;
+        self.withdraw_from(caller_address, Some(position_id), amount)
+    }
+
+    /// Withdraws `amount` from the position `position_id` of `staker` on behalf of a staker that
+    /// approved the caller as its operator.
+    fn withdraw_position_for(
+        &mut self,
+        staker: Address,
+        position_id: u64,
+        amount: U256,
+    ) -> Result<U256, Error> {
+        modifiers::without_position_nft()?;
+        self.only_staker_or_operator(staker)?;
+        self.withdraw_from(staker, Some(position_id), amount)
+    }
+
+    /// Withdraws the whole position `token_id` stands for to the caller, who must hold the NFT, and
+    /// burns it. The position is first moved to the caller if it was opened by someone else.
+    fn withdraw_nft(&mut self, token_id: u64) -> Result<U256, Error> {
+        let (caller_address, position_id, amount) = self.redeem_position_nft(token_id)?;
+        self.withdraw_from(caller_address, Some(position_id), amount)
+    }
+
+    /// Moves the position `token_id` stands for to the caller, who must hold the NFT, and burns it.
+    /// Returns the caller along with the id and amount of the position it now holds.
+    fn redeem_position_nft(&mut self, token_id: u64) -> Result<(Address, u64, U256), Error> {
+        let collection = self
+            .position_nft_metadata()
+            .ok_or(Error::InvalidPositionNft)?;
+        let (caller_address, position_id, amount) = self.take_position_nft(token_id)?;
+        PositionNfts::instance().remove_token_id(&Key::from(caller_address), position_id, token_id);
+        position_nft::burn(collection, token_id);
+        Ok((caller_address, position_id, amount))
+    }
+
+    /// Moves the position `token_id` stands for to the caller, who must hold the NFT, which then
+    /// stands for the moved position. Returns the caller along with the id and amount of the
+    /// position it now holds.
+    fn take_position_nft(&mut self, token_id: u64) -> Result<(Address, u64, U256), Error> {
+        let collection = self
+            .position_nft_metadata()
+            .ok_or(Error::InvalidPositionNft)?;
+        let position_nfts = PositionNfts::instance();
+        let (staker, position_id) = position_nfts
+            .position(token_id)
+            .ok_or(Error::UnknownPosition)?;
+        let caller_address = detail::get_immediate_caller_address()?;
+        let holder = Key::from(caller_address);
+        if position_nft::owner_of(collection, token_id) != holder {
+            return Err(Error::PermissionDenied);
+        }
 
         let stakers_dict = StakedTokens::instance();
+        let position = stakers_dict
+            .position(&staker, position_id)
+            .ok_or(Error::UnknownPosition)?;
+        let held_id = if staker == holder {
+            position_id
+        } else {
+            let holder_position_id = stakers_dict.position_count(&holder);
+            let staker_address = Address::try_from(staker)?;
+            self.move_stake(
+                staker_address,
+                caller_address,
+                Some(position_id),
+                position.amount,
+            )?;
+            position_nfts.remove_token_id(&staker, position_id, token_id);
+            position_nfts.set_token_id(&holder, holder_position_id, token_id);
+            holder_position_id
+        };
+        Ok((caller_address, held_id, position.amount))
+    }
+
+    /// Fails unless `staker` holds the NFTs of all its positions, so that rewards accrued on a
+    /// position are not claimed by the staker that opened it once its NFT changed hands.
+    fn only_position_nft_holder(&self, staker: Key) -> Result<(), Error> {
+        let collection = match self.position_nft_metadata() {
+            Some(collection) => collection,
+            None => return Ok(()),
+        };
+        let position_nfts = PositionNfts::instance();
+        for position in StakedTokens::instance().positions(&staker) {
+            let held = position_nfts
+                .token_id(&staker, position.id)
+                .map_or(false, |token_id| {
+                    position_nft::owner_of(collection, token_id) == staker
+                });
+            if !held {
+                return Err(Error::PositionNftRequired);
+            }
+        }
+        Ok(())
+    }
+
+    /// Token identifier of the NFT minted for the position `position_id` of `staker`.
+    fn position_token_id(&self, staker: Key, position_id: u64) -> Option<u64> {
+        PositionNfts::instance().token_id(&staker, position_id)
+    }
+
+    /// Allows `operator` to withdraw and claim on behalf of the caller.
+    fn approve_operator(&mut self, operator: Key) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ManageOperators)?;
+        let staker_address = detail::get_immediate_caller_address()?;
+        Operators::instance().set_operator(&Key::from(staker_address), &operator, true);
+        self.emit(StakingContractEvent::OperatorApproved {
+            staker_address,
+            operator,
+        });
+        Ok(())
+    }
+
+    fn revoke_operator(&mut self, operator: Key) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ManageOperators)?;
+        let staker_address = detail::get_immediate_caller_address()?;
+        Operators::instance().set_operator(&Key::from(staker_address), &operator, false);
+        self.emit(StakingContractEvent::OperatorRevoked {
+            staker_address,
+            operator,
+        });
+        Ok(())
+    }
+
+    fn is_operator(&self, staker: Key, operator: Key) -> bool {
+        Operators::instance().is_operator(&staker, &operator)
+    }
+
+    /// Sends the payouts of the caller to `destination`, or back to the caller when `None`.
+    fn set_payout_destination(&mut self, destination: Option<Key>) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ManageOperators)?;
+        if let Some(destination) = destination {
+            Address::try_from(destination)?;
+        }
+        let staker_address = detail::get_immediate_caller_address()?;
+        Operators::instance().set_payout_destination(&Key::from(staker_address), destination);
+        Ok(())
+    }
+
+    fn payout_destination(&self, staker: Key) -> Option<Key> {
+        Operators::instance().payout_destination(&staker)
+    }
+
+    /// Address the withdrawals and rewards of `staker_address` are paid out to.
+    fn payout_address(&self, staker_address: Address) -> Address {
+        Operators::instance()
+            .payout_destination(&Key::from(staker_address))
+            .and_then(|destination| Address::try_from(destination).ok())
+            .unwrap_or(staker_address)
+    }
+
+    /// Fails unless the caller is `staker_address` or an operator it approved.
+    fn only_staker_or_operator(&self, staker_address: Address) -> Result<(), Error> {
         let caller_address = detail::get_immediate_caller_address()?;
+        if caller_address == staker_address
+            || self.is_operator(Key::from(staker_address), Key::from(caller_address))
+        {
+            Ok(())
+        } else {
+            Err(Error::PermissionDenied)
+        }
+    }
+
+    /// Withdraws `amount` staked by `staker_address`, from the position `position_id` or else from
+    /// its unlocked positions, oldest first, and pays it out to the payout address of the staker.
+    fn withdraw_from(
+        &mut self,
+        staker_address: Address,
+        position_id: Option<u64>,
+        amount: U256,
+    ) -> Result<U256, Error> {
+        modifiers::in_phase(Operation::Withdraw)?;
+        modifiers::positive(amount)?;
+
+        let stakers_dict = StakedTokens::instance();
 
         if amount
             > stakers_dict
-                .get_amount_staked_by_address(&Key::from(caller_address))
-                .unwrap()
+                .get_amount_staked_by_address(&Key::from(staker_address))
+                .ok_or(Error::NotAStaker)?
         {
             return Err(Error::NotRequiredStake);
         }
 
-        if runtime::get_blocktime() < BlockTime::new(self.staking_ends()) {
-            self.withdraw_early(amount, caller_address)
+        let staker = Key::from(staker_address);
+        if self.reward_mode() == RewardMode::Streaming {
+            self.checkpoint(&staker)?;
+        } else if self.reward_mode() == RewardMode::Vault {
+            self.compound()?;
+        }
+        let staked_weight = stakers_dict.weighted_stake(&staker);
+        let now = u64::from(runtime::get_blocktime());
+        let weight = match position_id {
+            Some(id) => {
+                stakers_dict.withdraw_position(&staker, id, &amount, now, &self.lock_tiers())?
+            }
+            None => stakers_dict.withdraw_stake(&staker, &amount, now, &self.lock_tiers())?,
+        };
+        data::set_outstanding_stake(data::outstanding_stake().safe_sub(amount)?);
+        receipt::burn(staker_address, amount)?;
+
+        // Vault amounts are shares, withdrawn at the current share price.
+        let assets = if self.reward_mode() == RewardMode::Vault {
+            self.burn_shares(amount)?
         } else {
-            self.withdraw_after_close(amount, caller_address)
+            amount
+        };
+        let penalty = self.early_withdraw_penalty(assets)?;
+        if self.reward_mode() == RewardMode::Streaming {
+            self.withdraw_streaming(amount, penalty, weight, staker_address)?;
+        } else if self.reward_mode() == RewardMode::Vault {
+            self.withdraw_vault(amount, assets, penalty, weight, staker_address)?;
+        } else if runtime::get_blocktime() < BlockTime::new(self.withdraw_ends()) {
+            self.withdraw_early(amount, penalty, weight, staked_weight, staker_address)?;
+        } else {
+            self.withdraw_after_close(amount, penalty, weight, staked_weight, staker_address)?;
         }
+        self.forfeit(staker_address, penalty)?;
+        Ok(amount)
     }
 
-    fn withdraw_early(&mut self, amount: U256, caller_address: Address) -> Result<U256, Error> {
-        let staker_address = detail::get_immediate_caller_address()
-            .unwrap_or_revert_with(Error::ImmediateCallerAddressFail);
-        let token_address = self.address();
+    /// Penalty charged on `amount` withdrawn now.
+    fn early_withdraw_penalty(&self, amount: U256) -> Result<U256, Error> {
+        penalty::early_withdraw_penalty(
+            amount,
+            self.penalty().rate,
+            u64::from(runtime::get_blocktime()),
+            self.staking_ends(),
+            self.withdraw_ends(),
+        )
+    }
+
+    /// Routes the `amount` of staked tokens forfeited by `staker_address` as configured by the
+    /// penalty mode of the pool.
+    fn forfeit(&mut self, staker_address: Address, amount: U256) -> Result<(), Error> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let penalty = self.penalty();
+        match penalty.mode {
+            PenaltyMode::Treasury => {
+                let treasury = penalty.treasury.ok_or(Error::InvalidPenalty)?;
+                self.pay_direct(treasury, amount)?;
+            }
+            PenaltyMode::Redistribute => self.redistribute(amount)?,
+            PenaltyMode::Burn => self.burn(self.erc20_metadata(), amount),
+        }
+        self.emit(StakingContractEvent::EarlyWithdrawPenalty {
+            token_address: self.address(),
+            staker_address,
+            amount,
+            penalty_mode: penalty.mode,
+        });
+        Ok(())
+    }
 
-        let denom = U256::from(self.withdraw_ends() - self.staking_ends()) * self.staking_total();
+    /// Adds `amount` to the rewards of the stakers left in the pool. In streaming mode it is shared
+    /// at once by the current stakers, otherwise it is paid out along with the reward balance.
+    fn redistribute(&mut self, amount: U256) -> Result<(), Error> {
+        if self.reward_mode() == RewardMode::Vault && !self.staked_balance().is_zero() {
+            data::set_vault_assets(data::vault_assets().safe_add(amount)?);
+            return Ok(());
+        }
+        if self.reward_mode() == RewardMode::Streaming && !self.weighted_balance().is_zero() {
+            self.update_reward_per_token()?;
+            data::set_reward_per_token_stored(self.reward_per_token_stored().safe_add(
+                safe_math::mul_div(
+                    amount,
+                    U256::from(reward::REWARD_PRECISION),
+                    self.weighted_balance(),
+                )?,
+            )?);
+        }
+        self.set_total_reward(self.total_reward().safe_add(amount)?);
+        self.set_reward_balance(self.reward_balance().safe_add(amount)?);
+        Ok(())
+    }
 
-        let reward: U256 =
-            U256::from(u64::from(runtime::get_blocktime()) - self.staking_ends()) * amount / denom;
+    /// Pays out `amount` withdrawn before `withdraw_ends` from positions worth `weight` out of the
+    /// `staked_weight` of `staker_address`, less the `penalty` forfeited.
+    fn withdraw_early(
+        &mut self,
+        amount: U256,
+        penalty: U256,
+        weight: U256,
+        staked_weight: U256,
+        staker_address: Address,
+    ) -> Result<U256, Error> {
+        let token_address = self.address();
+        let staker = Key::from(staker_address);
+        let staker_rewards = StakerRewards::instance();
 
-        let pay_out = amount + reward;
+        let prepaid_rewards = staker_rewards.prepaid_rewards(&staker);
+        let reward = self.withdrawn_reward(&staker, weight, staked_weight)?;
+        let settled = prepaid_rewards.safe_sub(staker_rewards.prepaid_rewards(&staker))?;
+        let paid_amount = amount.safe_sub(penalty)?;
 
-        self.set_reward_balance(self.reward_balance() - reward);
-        self.set_staked_balance(self.staked_balance() - amount);
-        let stakers_dict = StakedTokens::instance();
-        stakers_dict.withdraw_stake(&Key::from(caller_address), &amount)?;
-        self.pay_direct(caller_address, pay_out)?;
+        // Rewards claimed on the withdrawn positions leave the reward balance along with them.
+        self.set_reward_balance(self.reward_balance().safe_sub(reward.safe_add(settled)?)?);
+        self.set_staked_balance(self.staked_balance().safe_sub(amount)?);
+        self.set_weighted_balance(self.weighted_balance().safe_sub(weight)?);
+        let reward = self.credit_referrer(staker_address, reward)?;
+        self.pay_out(self.payout_address(staker_address), paid_amount, reward)?;
         self.emit(StakingContractEvent::PaidOut {
             staker_address,
             token_address,
-            amount,
+            amount: paid_amount,
             reward,
         });
         Ok(amount)
     }
 
+    /// Pays out `amount` withdrawn from `withdraw_ends` on from positions worth `weight` out of the
+    /// `staked_weight` of `staker_address`, less the `penalty` forfeited.
     fn withdraw_after_close(
         &mut self,
         amount: U256,
-        caller_address: Address,
+        penalty: U256,
+        weight: U256,
+        staked_weight: U256,
+        staker_address: Address,
     ) -> Result<U256, Error> {
-        let staker_address = detail::get_immediate_caller_address()
-            .unwrap_or_revert_with(Error::ImmediateCallerAddressFail);
         let token_address = self.address();
+        let staker = Key::from(staker_address);
 
-        let reward = self.reward_balance() * amount / self.staked_balance();
-        let pay_out = amount + reward;
-        let stakers_dict = StakedTokens::instance();
-        stakers_dict.withdraw_stake(&Key::from(caller_address), &amount)?;
-        self.pay_direct(caller_address, pay_out)?;
+        let reward = self.withdrawn_reward(&staker, weight, staked_weight)?;
+        let paid_amount = amount.safe_sub(penalty)?;
+        let reward = self.credit_referrer(staker_address, reward)?;
+        self.pay_out(self.payout_address(staker_address), paid_amount, reward)?;
         self.emit(StakingContractEvent::PaidOut {
             staker_address,
             token_address,
-            amount,
+            amount: paid_amount,
+            reward,
+        });
+        Ok(amount)
+    }
+
+    /// Pays out `amount` withdrawn from positions worth `weight`, less the `penalty` forfeited,
+    /// along with the rewards streamed to `staker_address`, which must have been checkpointed before
+    /// its positions changed.
+    fn withdraw_streaming(
+        &mut self,
+        amount: U256,
+        penalty: U256,
+        weight: U256,
+        staker_address: Address,
+    ) -> Result<U256, Error> {
+        let token_address = self.address();
+        let staker = Key::from(staker_address);
+
+        let staker_rewards = StakerRewards::instance();
+        let reward = staker_rewards.pending_rewards(&staker);
+        staker_rewards.set_pending_rewards(&staker, U256::zero());
+
+        let paid_amount = amount.safe_sub(penalty)?;
+
+        self.set_reward_balance(self.reward_balance().safe_sub(reward)?);
+        self.set_staked_balance(self.staked_balance().safe_sub(amount)?);
+        self.set_weighted_balance(self.weighted_balance().safe_sub(weight)?);
+        self.sync_reward_debt(&staker)?;
+
+        let reward = self.credit_referrer(staker_address, reward)?;
+        self.pay_out(self.payout_address(staker_address), paid_amount, reward)?;
+        self.emit(StakingContractEvent::PaidOut {
+            staker_address,
+            token_address,
+            amount: paid_amount,
             reward,
         });
         Ok(amount)
     }
 
+    /// Pays out the rewards accrued by the caller while keeping its principal staked.
+    fn claim_rewards(&mut self) -> Result<U256, Error> {
+        let caller_address = detail::get_immediate_caller_address()?;
+        self.claim_rewards_of(caller_address)
+    }
+
+    /// Pays out the rewards accrued by `staker` on behalf of a staker that approved the caller as
+    /// its operator.
+    fn claim_rewards_for(&mut self, staker: Address) -> Result<U256, Error> {
+        self.only_staker_or_operator(staker)?;
+        self.claim_rewards_of(staker)
+    }
+
+    /// Moves the position `token_id` stands for to the caller, who must hold the NFT, and pays out
+    /// the rewards the caller accrued, keeping the NFT and the principal staked.
+    fn claim_rewards_nft(&mut self, token_id: u64) -> Result<U256, Error> {
+        let (caller_address, _, _) = self.take_position_nft(token_id)?;
+        self.claim_rewards_of(caller_address)
+    }
+
+    /// Pays out the rewards accrued by `staker_address` to its payout address.
+    fn claim_rewards_of(&mut self, staker_address: Address) -> Result<U256, Error> {
+        modifiers::in_phase(Operation::ClaimRewards)?;
+        // Vault rewards are compounded into the shares and only paid out on redemption.
+        if self.reward_mode() == RewardMode::Vault {
+            return Err(Error::InvalidRewardMode);
+        }
+
+        let staker = Key::from(staker_address);
+        self.only_position_nft_holder(staker)?;
+        self.amount_staked(staker)?;
+        let weight = StakedTokens::instance().weighted_stake(&staker);
+        let token_address = self.address();
+        let staker_rewards = StakerRewards::instance();
+
+        let reward = if self.reward_mode() == RewardMode::Streaming {
+            self.checkpoint(&staker)?;
+            let reward = staker_rewards.pending_rewards(&staker);
+            staker_rewards.set_pending_rewards(&staker, U256::zero());
+            self.set_reward_balance(self.reward_balance().safe_sub(reward)?);
+            reward
+        } else {
+            let prepaid_rewards = staker_rewards.prepaid_rewards(&staker);
+            let (reward, _) = self.pro_rata_reward(&staker, weight)?;
+            let reward = reward.saturating_sub(prepaid_rewards);
+            // The reward balance keeps counting claimed rewards until the stake they were claimed
+            // on is withdrawn, so that later shares of it are still computed on the same total.
+            staker_rewards.set_prepaid_rewards(&staker, prepaid_rewards.safe_add(reward)?);
+            reward
+        };
+
+        if reward.is_zero() {
+            return Ok(reward);
+        }
+        let reward = self.credit_referrer(staker_address, reward)?;
+
+        staker_rewards.set_claimed_rewards(
+            &staker,
+            staker_rewards.claimed_rewards(&staker).safe_add(reward)?,
+        );
+        data::set_distributed_rewards(data::distributed_rewards().safe_add(reward)?);
+        self.pay_reward(self.payout_address(staker_address), reward)?;
+        self.emit(StakingContractEvent::RewardClaimed {
+            token_address,
+            staker_address,
+            reward,
+        });
+        Ok(reward)
+    }
+
+    fn claimed_rewards(&self, staker: Key) -> U256 {
+        StakerRewards::instance().claimed_rewards(&staker)
+    }
+
+    fn schema_version(&self) -> u32 {
+        data::schema_version()
+    }
+
+    /// Brings the storage written by a previous contract version up to
+    /// [`data::CURRENT_SCHEMA_VERSION`]. Does nothing when the storage is already up to date.
+    /// Storage without a schema version predates upgrades and is not supported.
+    ///
+    /// Guarded by the package access URef of the upgrade session rather than by a role.
+    fn migrate(&mut self) -> Result<(), Error> {
+        modifiers::in_phase(Operation::Migrate)?;
+        let schema_version = self.schema_version();
+        if schema_version == 0 || schema_version > data::CURRENT_SCHEMA_VERSION {
+            return Err(Error::UnsupportedSchemaVersion);
+        }
+        // Layout changes are applied here, one `if schema_version < n` step per schema version
+        // bumped. Version 1 is the first one, so there is nothing to convert yet.
+        data::set_schema_version(data::CURRENT_SCHEMA_VERSION);
+        Ok(())
+    }
+
+    fn paused(&self) -> bool {
+        data::paused()
+    }
+
+    fn phase(&self) -> PoolPhase {
+        modifiers::phase()
+    }
+
+    fn pause(&mut self) -> Result<(), Error> {
+        self.only_role(Role::Pauser)?;
+        modifiers::in_phase(Operation::Pause)?;
+        data::set_paused(true);
+        self.emit(StakingContractEvent::Paused {
+            account: self.get_caller(),
+        });
+        Ok(())
+    }
+
+    fn unpause(&mut self) -> Result<(), Error> {
+        self.only_role(Role::Pauser)?;
+        modifiers::in_phase(Operation::Unpause)?;
+        data::set_paused(false);
+        self.emit(StakingContractEvent::Unpaused {
+            account: self.get_caller(),
+        });
+        Ok(())
+    }
+
+    /// Pays back the whole stake of the caller while the pool is paused, forfeiting its rewards.
+    fn emergency_withdraw(&mut self) -> Result<U256, Error> {
+        modifiers::in_phase(Operation::EmergencyWithdraw)?;
+        modifiers::without_position_nft()?;
+
+        let caller_address = detail::get_immediate_caller_address()?;
+        let staker = Key::from(caller_address);
+        modifiers::positive(self.amount_staked(staker)?)?;
+        let token_address = self.address();
+
+        if self.reward_mode() == RewardMode::Streaming {
+            self.update_reward_per_token()?;
+        }
+        let staker_rewards = StakerRewards::instance();
+        // Rewards already claimed were paid out of the reward balance.
+        self.set_reward_balance(
+            self.reward_balance()
+                .safe_sub(staker_rewards.prepaid_rewards(&staker))?,
+        );
+        staker_rewards.set_pending_rewards(&staker, U256::zero());
+        staker_rewards.set_prepaid_rewards(&staker, U256::zero());
+        staker_rewards.set_reward_debt(&staker, U256::zero());
+
+        let (units, weight) = StakedTokens::instance().withdraw_all(&staker);
+        let amount = if self.reward_mode() == RewardMode::Vault {
+            self.burn_shares(units)?
+        } else {
+            units
+        };
+        self.set_staked_balance(self.staked_balance().safe_sub(units)?);
+        self.set_weighted_balance(self.weighted_balance().safe_sub(weight)?);
+        data::set_outstanding_stake(data::outstanding_stake().safe_sub(units)?);
+        receipt::burn(caller_address, units)?;
+        self.pay_direct(caller_address, amount)?;
+        self.emit(StakingContractEvent::EmergencyWithdraw {
+            token_address,
+            staker_address: caller_address,
+            amount,
+        });
+        Ok(amount)
+    }
+
+    /// Pays back the whole position `token_id` stands for to the caller, who must hold the NFT,
+    /// while the pool is paused, regardless of its lock and forfeiting its rewards, and burns the
+    /// NFT.
+    fn emergency_withdraw_nft(&mut self, token_id: u64) -> Result<U256, Error> {
+        modifiers::in_phase(Operation::EmergencyWithdraw)?;
+        let (caller_address, position_id, units) = self.redeem_position_nft(token_id)?;
+        modifiers::positive(units)?;
+        let staker = Key::from(caller_address);
+        let token_address = self.address();
+
+        if self.reward_mode() == RewardMode::Streaming {
+            self.checkpoint(&staker)?;
+        }
+        let stakers_dict = StakedTokens::instance();
+        let staked_weight = stakers_dict.weighted_stake(&staker);
+        let weight = stakers_dict.release_position(&staker, position_id, &self.lock_tiers())?;
+
+        // Rewards already claimed on the position were paid out of the reward balance.
+        let staker_rewards = StakerRewards::instance();
+        let prepaid_rewards = staker_rewards.prepaid_rewards(&staker);
+        if !prepaid_rewards.is_zero() && !staked_weight.is_zero() {
+            let settled = safe_math::mul_div(prepaid_rewards, weight, staked_weight)?;
+            staker_rewards.set_prepaid_rewards(&staker, prepaid_rewards.safe_sub(settled)?);
+            self.set_reward_balance(self.reward_balance().safe_sub(settled)?);
+        }
+        if self.reward_mode() == RewardMode::Streaming {
+            self.sync_reward_debt(&staker)?;
+        }
+
+        let amount = if self.reward_mode() == RewardMode::Vault {
+            self.burn_shares(units)?
+        } else {
+            units
+        };
+        self.set_staked_balance(self.staked_balance().safe_sub(units)?);
+        self.set_weighted_balance(self.weighted_balance().safe_sub(weight)?);
+        data::set_outstanding_stake(data::outstanding_stake().safe_sub(units)?);
+        receipt::burn(caller_address, units)?;
+        self.pay_direct(caller_address, amount)?;
+        self.emit(StakingContractEvent::EmergencyWithdraw {
+            token_address,
+            staker_address: caller_address,
+            amount,
+        });
+        Ok(amount)
+    }
+
     fn add_reward(
         &mut self,
         reward_amount: U256,
         withdrawable_amount: U256,
     ) -> Result<U256, Error> {
-        modifiers::before(self.withdraw_starts())?;
+        self.only_role(Role::RewardManager)?;
+        modifiers::in_phase(Operation::AddReward)?;
 
         if reward_amount <= U256::from(0u64) {
             return Err(Error::NegativeReward);
@@ -261,80 +1270,612 @@ pub trait CEP20STK<Storage: ContractStorage>: ContractContext<Storage> {
         if withdrawable_amount > reward_amount {
             return Err(Error::NegativeWithdrawableReward);
         }
-        self.pay_me(detail::get_immediate_caller_address()?, reward_amount);
+        self.pay_me_reward(detail::get_immediate_caller_address()?, reward_amount);
+
+        if matches!(
+            self.reward_mode(),
+            RewardMode::Streaming | RewardMode::Vault
+        ) {
+            self.notify_reward(reward_amount)?;
+        }
 
-        let current_total_reward = self.total_reward() + reward_amount;
+        let current_total_reward = self.total_reward().safe_add(reward_amount)?;
 
         self.set_total_reward(current_total_reward);
         self.set_reward_balance(current_total_reward);
-        self.set_early_withdraw_reward(self.early_withdraw_reward() + withdrawable_amount);
+        self.set_early_withdraw_reward(self.early_withdraw_reward().safe_add(withdrawable_amount)?);
+        self.emit(StakingContractEvent::AddReward {
+            reward_amount,
+            withdrawable_amount,
+        });
 
         Ok(reward_amount)
     }
 
+    fn grant_role(&mut self, role: Role, account: Key) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ManageRoles)?;
+        self.only_role(Role::Owner)?;
+        if RoleControl::has_role(self, role as u8, account) {
+            return Ok(());
+        }
+        self.grant_role_without_checked(role as u8, account);
+        self.emit(StakingContractEvent::RoleGranted {
+            role,
+            account,
+            sender: self.get_caller(),
+        });
+        Ok(())
+    }
+
+    fn revoke_role(&mut self, role: Role, account: Key) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ManageRoles)?;
+        self.only_role(Role::Owner)?;
+        self.remove_role(role, account);
+        Ok(())
+    }
+
+    /// Gives up `role` held by the caller.
+    fn renounce_role(&mut self, role: Role) -> Result<(), Error> {
+        modifiers::in_phase(Operation::ManageRoles)?;
+        self.only_role(role)?;
+        self.remove_role(role, self.get_caller());
+        Ok(())
+    }
+
+    fn remove_role(&mut self, role: Role, account: Key) {
+        if !RoleControl::has_role(self, role as u8, account) {
+            return;
+        }
+        self.revoke_role_without_checked(role as u8, account);
+        self.emit(StakingContractEvent::RoleRevoked {
+            role,
+            account,
+            sender: self.get_caller(),
+        });
+    }
+
+    fn only_role(&self, role: Role) -> Result<(), Error> {
+        if RoleControl::has_role(self, role as u8, self.get_caller()) {
+            Ok(())
+        } else {
+            Err(Error::PermissionDenied)
+        }
+    }
+
     fn staker_reward(&mut self, staker_address: Key) -> Result<U256, Error> {
-        let amount = self.amount_staked(staker_address)?;
-        let reward: U256 = if runtime::get_blocktime() < BlockTime::new(self.staking_ends()) {
-            let denom =
-                U256::from(self.withdraw_ends() - self.staking_ends()) * self.staking_total();
+        self.amount_staked(staker_address)?;
+        if self.reward_mode() == RewardMode::Streaming {
+            return self.earned(&staker_address);
+        }
+        // Vault rewards are compounded into the share price instead.
+        if self.reward_mode() == RewardMode::Vault {
+            return Ok(U256::zero());
+        }
+        // Nothing is earned before `staking_ends`, where the early withdraw reward starts at zero.
+        if runtime::get_blocktime() < BlockTime::new(self.staking_ends()) {
+            return Ok(U256::zero());
+        }
+        let weight = StakedTokens::instance().weighted_stake(&staker_address);
+        let prepaid_rewards = StakerRewards::instance().prepaid_rewards(&staker_address);
+        let (reward, _) = self.pro_rata_reward(&staker_address, weight)?;
+        Ok(reward.saturating_sub(prepaid_rewards))
+    }
 
-            U256::from(u64::from(runtime::get_blocktime()) - self.staking_ends()) * amount / denom
+    /// Reward owed to `staker` on stakes worth `weight` by the pro-rata reward modes, along with
+    /// the fraction of a token left over in fixed-point mode, scaled by
+    /// [`reward::REWARD_PRECISION`].
+    fn pro_rata_reward(&self, staker: &Key, weight: U256) -> Result<(U256, U256), Error> {
+        if self.reward_mode() != RewardMode::FixedPoint {
+            return Ok((self.pro_rata_share(weight, U256::one())?, U256::zero()));
+        }
+        let precision = U256::from(reward::REWARD_PRECISION);
+        let scaled_reward = self
+            .pro_rata_share(weight, precision)?
+            .safe_add(StakerRewards::instance().reward_remainder(staker))?;
+        Ok((
+            scaled_reward.safe_div(precision)?,
+            scaled_reward % precision,
+        ))
+    }
+
+    /// Share of the rewards owed on stakes worth `weight`, multiplied by `scale`, as computed by
+    /// `Staking.sol`: a share of `early_withdraw_reward` growing over the withdraw window before
+    /// `withdraw_ends`, and a share of the reward balance from then on.
+    fn pro_rata_share(&self, weight: U256, scale: U256) -> Result<U256, Error> {
+        if runtime::get_blocktime() < BlockTime::new(self.withdraw_ends()) {
+            reward::early_withdraw_reward(
+                self.early_withdraw_reward().safe_mul(scale)?,
+                weight,
+                data::weighted_total(),
+                u64::from(runtime::get_blocktime()),
+                self.staking_ends(),
+                self.withdraw_ends(),
+            )
         } else {
-            self.reward_balance() * amount / self.staked_balance()
-        };
+            reward::after_close_reward(
+                self.reward_balance().safe_mul(scale)?,
+                weight,
+                self.weighted_balance(),
+            )
+        }
+    }
+
+    /// Pro-rata reward paid to `staker` for withdrawing positions worth `weight` out of its
+    /// `staked_weight`. In fixed-point mode the fraction of a token left over is carried over to
+    /// its next withdrawal.
+    fn withdrawn_reward(
+        &mut self,
+        staker: &Key,
+        weight: U256,
+        staked_weight: U256,
+    ) -> Result<U256, Error> {
+        let (reward, reward_remainder) = self.pro_rata_reward(staker, weight)?;
+        if self.reward_mode() == RewardMode::FixedPoint {
+            StakerRewards::instance().set_reward_remainder(staker, reward_remainder);
+        }
+        self.settle_prepaid_rewards(staker, weight, staked_weight, reward)
+    }
+
+    /// Installs the receipt token of the pool, named after the pool and using the decimals of the
+    /// staking token.
+    fn init_receipt(&mut self) {
+        let token = self.erc20_metadata();
+        let symbol: String =
+            runtime::call_versioned_contract(token, None, "symbol", runtime_args! {});
+        let decimals: u8 =
+            runtime::call_versioned_contract(token, None, "decimals", runtime_args! {});
+        receipt::init(format!("st{symbol}"), decimals);
+    }
+
+    /// Moves `amount` receipts of the caller to `recipient`, along with the stake they stand for.
+    fn transfer_receipt(&mut self, recipient: Address, amount: U256) -> Result<(), Error> {
+        modifiers::in_phase(Operation::TransferStake)?;
+        if !receipt::enabled() {
+            return Err(Error::NoReceiptToken);
+        }
+        let sender = detail::get_immediate_caller_address()?;
+        receipt::transfer(recipient, amount)?;
+        self.move_stake(sender, recipient, None, amount)
+    }
+
+    /// Moves `amount` receipts of `owner` to `recipient` out of the allowance of the caller, along
+    /// with the stake they stand for.
+    fn transfer_receipt_from(
+        &mut self,
+        owner: Address,
+        recipient: Address,
+        amount: U256,
+    ) -> Result<(), Error> {
+        modifiers::in_phase(Operation::TransferStake)?;
+        if !receipt::enabled() {
+            return Err(Error::NoReceiptToken);
+        }
+        receipt::transfer_from(owner, recipient, amount)?;
+        self.move_stake(owner, recipient, None, amount)
+    }
+
+    /// Moves `amount` staked by `sender`, out of its position `position_id` or oldest first, to
+    /// `recipient`. Positions keep their lock, and the rewards streamed so far stay with the sender
+    /// while the pro-rata rewards follow the stake. Fails with `NotAllowlisted` in pools restricted
+    /// to an allowlist, where the recipient never proved an allocation.
+    fn move_stake(
+        &mut self,
+        sender: Address,
+        recipient: Address,
+        position_id: Option<u64>,
+        amount: U256,
+    ) -> Result<(), Error> {
+        if sender == recipient || amount.is_zero() {
+            return Ok(());
+        }
+        modifiers::without_allowlist()?;
+        let from = Key::from(sender);
+        let to = Key::from(recipient);
+        if self.reward_mode() == RewardMode::Streaming {
+            self.checkpoint(&from)?;
+            self.checkpoint(&to)?;
+        }
+
+        let stakers_dict = StakedTokens::instance();
+        let staked_weight = stakers_dict.weighted_stake(&from);
+        let weight =
+            stakers_dict.transfer_stake(&from, &to, position_id, &amount, &self.lock_tiers())?;
+
+        // Rewards claimed ahead of withdrawing move with the share of the stake they were paid on.
+        let staker_rewards = StakerRewards::instance();
+        let prepaid_rewards = staker_rewards.prepaid_rewards(&from);
+        if !prepaid_rewards.is_zero() && !staked_weight.is_zero() {
+            let moved = safe_math::mul_div(prepaid_rewards, weight, staked_weight)?;
+            staker_rewards.set_prepaid_rewards(&from, prepaid_rewards.safe_sub(moved)?);
+            staker_rewards
+                .set_prepaid_rewards(&to, staker_rewards.prepaid_rewards(&to).safe_add(moved)?);
+        }
+
+        if self.reward_mode() == RewardMode::Streaming {
+            self.sync_reward_debt(&from)?;
+            self.sync_reward_debt(&to)?;
+        }
+        self.emit(StakingContractEvent::StakeTransferred {
+            token_address: self.address(),
+            sender,
+            recipient,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Pays out the `assets` that `shares` of the vault, from positions worth `weight`, were redeemed
+    /// for, less the `penalty` forfeited.
+    fn withdraw_vault(
+        &mut self,
+        shares: U256,
+        assets: U256,
+        penalty: U256,
+        weight: U256,
+        staker_address: Address,
+    ) -> Result<U256, Error> {
+        let paid_amount = assets.safe_sub(penalty)?;
+        self.set_staked_balance(self.staked_balance().safe_sub(shares)?);
+        self.set_weighted_balance(self.weighted_balance().safe_sub(weight)?);
+        self.pay_direct(self.payout_address(staker_address), paid_amount)?;
+        self.emit(StakingContractEvent::PaidOut {
+            staker_address,
+            token_address: self.address(),
+            amount: paid_amount,
+            reward: U256::zero(),
+        });
+        Ok(assets)
+    }
+
+    /// Redeems `shares` of the vault from the unlocked positions of the caller, oldest first.
+    fn redeem(&mut self, shares: U256) -> Result<U256, Error> {
+        if self.reward_mode() != RewardMode::Vault {
+            return Err(Error::InvalidRewardMode);
+        }
+        modifiers::without_position_nft()?;
+        let caller_address = detail::get_immediate_caller_address()?;
+        self.withdraw_from(caller_address, None, shares)
+    }
+
+    /// Vault shares held by `staker`.
+    fn shares_of(&self, staker: Key) -> U256 {
+        if self.reward_mode() != RewardMode::Vault {
+            return U256::zero();
+        }
+        StakedTokens::instance()
+            .get_amount_staked_by_address(&staker)
+            .unwrap_or_default()
+    }
+
+    /// Staking tokens one vault share is worth, scaled by [`reward::REWARD_PRECISION`], including
+    /// the rewards streamed since the last compounding.
+    fn price_per_share(&self) -> Result<U256, Error> {
+        let precision = U256::from(reward::REWARD_PRECISION);
+        let total_shares = self.staked_balance();
+        if total_shares.is_zero() {
+            return Ok(precision);
+        }
+        let vault_assets = data::vault_assets().safe_add(self.pending_compound()?)?;
+        safe_math::mul_div(vault_assets, precision, total_shares)
+    }
 
+    /// Folds the rewards streamed since the last update into the vault, raising the price of every
+    /// share, and returns them. Anyone may call it.
+    fn compound(&mut self) -> Result<U256, Error> {
+        modifiers::in_phase(Operation::Compound)?;
+        if self.reward_mode() != RewardMode::Vault {
+            return Err(Error::InvalidRewardMode);
+        }
+        let reward = self.pending_compound()?;
+        data::set_last_update_time(self.reward_time());
+        if reward.is_zero() {
+            return Ok(reward);
+        }
+        data::set_vault_assets(data::vault_assets().safe_add(reward)?);
+        self.set_reward_balance(self.reward_balance().safe_sub(reward)?);
+        data::set_distributed_rewards(data::distributed_rewards().safe_add(reward)?);
+        self.emit(StakingContractEvent::Compounded {
+            token_address: self.address(),
+            reward,
+        });
         Ok(reward)
     }
 
+    /// Rewards streamed to the vault since the last update. Nothing accrues while it holds no
+    /// shares.
+    fn pending_compound(&self) -> Result<U256, Error> {
+        if self.staked_balance().is_zero() {
+            return Ok(U256::zero());
+        }
+        reward::streamed_reward(
+            self.reward_rate(),
+            data::last_update_time(),
+            self.reward_time(),
+        )
+    }
+
+    /// Adds `assets` to the vault and returns the shares they are worth at the current price.
+    fn issue_shares(&mut self, assets: U256) -> Result<U256, Error> {
+        self.compound()?;
+        let total_shares = self.staked_balance();
+        let vault_assets = data::vault_assets();
+        let shares = if total_shares.is_zero() || vault_assets.is_zero() {
+            assets
+        } else {
+            safe_math::mul_div(assets, total_shares, vault_assets)?
+        };
+        if shares.is_zero() {
+            return Err(Error::NotRequiredStake);
+        }
+        data::set_vault_assets(vault_assets.safe_add(assets)?);
+        Ok(shares)
+    }
+
+    /// Takes out of the vault the assets `shares` are worth and returns them. The shares must still
+    /// be counted in the staked balance.
+    fn burn_shares(&mut self, shares: U256) -> Result<U256, Error> {
+        let vault_assets = data::vault_assets();
+        let assets = safe_math::mul_div(shares, vault_assets, self.staked_balance())?;
+        data::set_vault_assets(vault_assets.safe_sub(assets)?);
+        Ok(assets)
+    }
+
+    /// Reward tokens of the pool that were neither paid out nor swept yet. Once every staker has
+    /// fully withdrawn, what is left is rounding dust nobody can claim.
+    fn dust(&self) -> U256 {
+        self.total_reward()
+            .saturating_sub(data::distributed_rewards())
+    }
+
+    /// Pays the undistributable reward dust of the pool to the caller once staking is over and every
+    /// staker has fully withdrawn.
+    fn sweep_dust(&mut self) -> Result<U256, Error> {
+        self.only_role(Role::Owner)?;
+        modifiers::in_phase(Operation::SweepDust)?;
+        if !data::outstanding_stake().is_zero() {
+            return Err(Error::PoolNotEmpty);
+        }
+        let dust = self.dust();
+        if dust.is_zero() {
+            return Ok(dust);
+        }
+        let recipient = detail::get_immediate_caller_address()?;
+        data::set_distributed_rewards(data::distributed_rewards().safe_add(dust)?);
+        self.pay_reward(recipient, dust)?;
+        self.emit(StakingContractEvent::DustSwept {
+            token_address: self.address(),
+            recipient,
+            amount: dust,
+        });
+        Ok(dust)
+    }
+
+    /// Deducts from `reward` the share of the rewards `staker` already claimed that belongs to the
+    /// `weight` being withdrawn out of its `staked_weight`, so that the same reward is never paid
+    /// twice.
+    fn settle_prepaid_rewards(
+        &mut self,
+        staker: &Key,
+        weight: U256,
+        staked_weight: U256,
+        reward: U256,
+    ) -> Result<U256, Error> {
+        let staker_rewards = StakerRewards::instance();
+        let prepaid_rewards = staker_rewards.prepaid_rewards(staker);
+        if prepaid_rewards.is_zero() || staked_weight.is_zero() {
+            return Ok(reward);
+        }
+        let settled = safe_math::mul_div(prepaid_rewards, weight, staked_weight)?;
+        staker_rewards.set_prepaid_rewards(staker, prepaid_rewards.safe_sub(settled)?);
+        Ok(reward.saturating_sub(settled))
+    }
+
+    /// Block time clamped to the reward period, which spans the staking window.
+    fn reward_time(&self) -> u64 {
+        u64::from(runtime::get_blocktime()).clamp(self.staking_starts(), self.staking_ends())
+    }
+
+    fn reward_per_token(&self) -> Result<U256, Error> {
+        reward::reward_per_token(
+            self.reward_per_token_stored(),
+            self.reward_rate(),
+            data::last_update_time(),
+            self.reward_time(),
+            self.weighted_balance(),
+        )
+    }
+
+    /// Rewards streamed to `staker` and not yet paid out.
+    fn earned(&self, staker: &Key) -> Result<U256, Error> {
+        let weight = StakedTokens::instance().weighted_stake(staker);
+        let staker_rewards = StakerRewards::instance();
+        staker_rewards
+            .pending_rewards(staker)
+            .safe_add(reward::earned(
+                weight,
+                self.reward_per_token()?,
+                staker_rewards.reward_debt(staker),
+            )?)
+    }
+
+    /// Advances the reward accumulator to the current block time.
+    fn update_reward_per_token(&mut self) -> Result<(), Error> {
+        data::set_reward_per_token_stored(self.reward_per_token()?);
+        data::set_last_update_time(self.reward_time());
+        Ok(())
+    }
+
+    /// Advances the accumulator and moves the rewards accrued by `staker` to its pending rewards.
+    ///
+    /// Must be called before the staked amount of `staker` changes.
+    fn checkpoint(&mut self, staker: &Key) -> Result<(), Error> {
+        let pending_rewards = self.earned(staker)?;
+        self.update_reward_per_token()?;
+        StakerRewards::instance().set_pending_rewards(staker, pending_rewards);
+        Ok(())
+    }
+
+    /// Resets the reward debt of `staker` to the current weight of its stake.
+    ///
+    /// Must be called after the staked amount of `staker` changes.
+    fn sync_reward_debt(&mut self, staker: &Key) -> Result<(), Error> {
+        let weight = StakedTokens::instance().weighted_stake(staker);
+        StakerRewards::instance().set_reward_debt(
+            staker,
+            reward::reward_debt(weight, self.reward_per_token_stored())?,
+        );
+        Ok(())
+    }
+
+    /// Spreads `reward_amount`, on top of what is left of the current stream, over the remainder of
+    /// the reward period.
+    fn notify_reward(&mut self, reward_amount: U256) -> Result<(), Error> {
+        if self.reward_mode() == RewardMode::Vault {
+            self.compound()?;
+        } else {
+            self.update_reward_per_token()?;
+        }
+        let remaining = self.staking_ends().safe_sub(self.reward_time())?;
+        let reward_rate = reward::reward_rate(reward_amount, self.reward_rate(), remaining)?;
+        data::set_reward_rate(reward_rate);
+        Ok(())
+    }
+
     fn pay_direct(&self, recipient: Address, amount: U256) -> Result<(), Error> {
         // modifiers::positive(amount)?;
-        let erc20_contract_package_hash = self.erc20_metadata();
+        self.transfer(self.erc20_metadata(), recipient, amount);
+        Ok(())
+    }
+
+    fn pay_to(&self, allower: Address, recipient: Address, amount: U256) {
+        self.transfer_from(self.erc20_metadata(), allower, recipient, amount);
+    }
+
+    fn pay_me(&self, payer: Address, amount: U256) {
+        self.pay_to(payer, self.stacking_contract_address(), amount)
+    }
+
+    /// Pays `amount` of reward tokens to `recipient`.
+    fn pay_reward(&self, recipient: Address, amount: U256) -> Result<(), Error> {
+        self.transfer(self.reward_token_metadata(), recipient, amount);
+        Ok(())
+    }
+
+    /// Pulls `amount` of reward tokens from the allowance `payer` gave to this contract.
+    fn pay_me_reward(&self, payer: Address, amount: U256) {
+        self.transfer_from(
+            self.reward_token_metadata(),
+            payer,
+            self.stacking_contract_address(),
+            amount,
+        )
+    }
+
+    /// Pays back `amount` of staked tokens along with `reward` reward tokens, in a single transfer
+    /// when both are the same token.
+    fn pay_out(&self, recipient: Address, amount: U256, reward: U256) -> Result<(), Error> {
+        data::set_distributed_rewards(data::distributed_rewards().safe_add(reward)?);
+        if self.erc20_metadata() == self.reward_token_metadata() {
+            return self.pay_direct(recipient, amount.safe_add(reward)?);
+        }
+        self.pay_direct(recipient, amount)?;
+        if !reward.is_zero() {
+            self.pay_reward(recipient, reward)?;
+        }
+        Ok(())
+    }
 
+    fn transfer(&self, token: ContractPackageHash, recipient: Address, amount: U256) {
         let args = runtime_args! {
             "recipient" => recipient,
             "amount" => amount,
         };
-        runtime::call_versioned_contract::<()>(erc20_contract_package_hash, None, "transfer", args);
-        Ok(())
+        runtime::call_versioned_contract::<()>(token, None, "transfer", args);
     }
 
-    fn pay_to(&self, allower: Address, recipient: Address, amount: U256) {
-        let erc20_contract_package_hash = self.erc20_metadata();
+    /// Burns `amount` of `token` held by this contract.
+    fn burn(&self, token: ContractPackageHash, amount: U256) {
+        let args = runtime_args! {
+            "owner" => self.stacking_contract_address(),
+            "amount" => amount,
+        };
+        runtime::call_versioned_contract::<()>(token, None, "burn", args);
+    }
+
+    fn transfer_from(
+        &self,
+        token: ContractPackageHash,
+        allower: Address,
+        recipient: Address,
+        amount: U256,
+    ) {
         let args = runtime_args! {
             "owner" => allower,
             "recipient" => recipient,
             "amount" => amount
         };
-        runtime::call_versioned_contract::<()>(
-            erc20_contract_package_hash,
-            None,
-            "transfer_from",
-            args,
-        );
+        runtime::call_versioned_contract::<()>(token, None, "transfer_from", args);
     }
 
-    fn pay_me(&self, payer: Address, amount: U256) {
+    /// Submits to `token` the permit `signature` of the owner of `public_key` approving `spender`.
+    fn permit(
+        &self,
+        token: ContractPackageHash,
+        public_key: PublicKey,
+        spender: Address,
+        amount: U256,
+        deadline: u64,
+        signature: Signature,
+    ) {
+        let args = runtime_args! {
+            "public_key" => public_key,
+            "spender" => spender,
+            "amount" => amount,
+            "deadline" => deadline,
+            "signature" => signature,
+        };
+        runtime::call_versioned_contract::<()>(token, None, "permit", args);
+    }
+
+    fn stacking_contract_address(&self) -> Address {
         #[allow(clippy::redundant_closure)]
         let stacking_contract_package_hash = runtime::get_key("stacking_contract_package_hash")
             .unwrap_or_revert_with(Error::MissingContractPackageHash)
             .into_hash()
             .map(|hash_address| ContractPackageHash::new(hash_address))
             .unwrap_or_revert_with(Error::InvalidContractPackageHash);
-        self.pay_to(
-            payer,
-            crate::address::Address::ContractPackage(stacking_contract_package_hash),
-            amount,
-        )
+        Address::ContractPackage(stacking_contract_package_hash)
     }
 
     fn emit(&mut self, event: StakingContractEvent) {
         data::emit(&event);
     }
 
+    /// Package hash of the CEP-78 collection the positions of the pool are minted in, if any.
+    fn position_nft(&self) -> Option<Key> {
+        data::position_nft()
+    }
+
+    fn position_nft_metadata(&self) -> Option<ContractPackageHash> {
+        data::position_nft().map(|collection| {
+            collection
+                .into_hash()
+                .map(ContractPackageHash::new)
+                .unwrap_or_revert_with(Error::InvalidPositionNft)
+        })
+    }
+
     fn erc20_metadata(&self) -> ContractPackageHash {
         #[allow(clippy::redundant_closure)]
-        runtime::get_key("erc20_contract_package_hash")
-            .unwrap_or_revert_with(Error::MissingContractPackageHash)
+        data::erc20_contract_package_hash()
+            .into_hash()
+            .map(|hash_address| ContractPackageHash::new(hash_address))
+            .unwrap_or_revert_with(Error::InvalidContractHash)
+    }
+
+    fn reward_token_metadata(&self) -> ContractPackageHash {
+        #[allow(clippy::redundant_closure)]
+        data::reward_token_package_hash()
             .into_hash()
             .map(|hash_address| ContractPackageHash::new(hash_address))
             .unwrap_or_revert_with(Error::InvalidContractHash)
@@ -0,0 +1,80 @@
+//! Tests of the Merkle allowlists of `staking_contract::allowlist`.
+use crate::staking_contract_tests::create_blake2b_hash;
+use casper_types::{account::AccountHash, Key, U256};
+use staking_contract::{
+    allowlist::{leaf_preimage, AllowlistProof, Hash, MerkleTree},
+    error::Error,
+};
+
+fn blake2b(bytes: &[u8]) -> Hash {
+    create_blake2b_hash(bytes)
+}
+
+fn entries() -> Vec<(Key, U256)> {
+    (1u8..=5)
+        .map(|i| {
+            (
+                Key::Account(AccountHash::new([i; 32])),
+                U256::from(i as u64 * 100),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_every_entry_proves_its_allocation() {
+    let entries = entries();
+    let tree = MerkleTree::new(&entries, blake2b).unwrap();
+    for (index, (staker, allocation)) in entries.iter().enumerate() {
+        let proof = AllowlistProof {
+            allocation: *allocation,
+            proof: tree.proof(index).unwrap(),
+        };
+        assert_eq!(
+            proof
+                .verify(&tree.root(), staker, blake2b)
+                .map_err(|e| e as u16),
+            Ok(())
+        );
+    }
+    assert_eq!(tree.proof(entries.len()), None);
+}
+
+#[test]
+fn test_proofs_do_not_prove_other_allocations_or_stakers() {
+    let entries = entries();
+    let tree = MerkleTree::new(&entries, blake2b).unwrap();
+    let (staker, allocation) = entries[2];
+    let proof = tree.proof(2).unwrap();
+
+    let raised = AllowlistProof {
+        allocation: allocation + 1,
+        proof: proof.clone(),
+    };
+    assert_eq!(
+        raised
+            .verify(&tree.root(), &staker, blake2b)
+            .map_err(|e| e as u16),
+        Err(Error::NotAllowlisted as u16)
+    );
+    let borrowed = AllowlistProof { allocation, proof };
+    assert_eq!(
+        borrowed
+            .verify(&tree.root(), &entries[3].0, blake2b)
+            .map_err(|e| e as u16),
+        Err(Error::NotAllowlisted as u16)
+    );
+}
+
+#[test]
+fn test_single_entry_tree_has_its_leaf_as_root() {
+    let entries = &entries()[..1];
+    let tree = MerkleTree::new(entries, blake2b).unwrap();
+    let (staker, allocation) = entries[0];
+    assert_eq!(
+        tree.root(),
+        blake2b(&leaf_preimage(&staker, allocation).unwrap())
+    );
+    assert_eq!(tree.proof(0), Some(vec![]));
+    assert!(MerkleTree::new(&[], blake2b).is_err());
+}
@@ -1,2 +1,29 @@
 #[cfg(test)]
 pub mod staking_contract_tests;
+
+#[cfg(test)]
+pub mod reward_differential_tests;
+
+#[cfg(test)]
+pub mod rng;
+
+#[cfg(test)]
+pub mod safe_math_tests;
+
+#[cfg(test)]
+pub mod position_nft_tests;
+
+#[cfg(test)]
+pub mod schedule_tests;
+
+#[cfg(test)]
+pub mod phase_tests;
+
+#[cfg(test)]
+pub mod limit_tests;
+
+#[cfg(test)]
+pub mod allowlist_tests;
+
+#[cfg(test)]
+pub mod referral_tests;
@@ -0,0 +1,64 @@
+//! Tests of the stake limits of `staking_contract::limit`.
+use casper_types::U256;
+use staking_contract::{error::Error, limit::StakeLimits};
+
+fn code(result: Result<(), Error>) -> Result<(), u16> {
+    result.map_err(|error| error as u16)
+}
+
+#[test]
+fn test_check_amount_enforces_the_minimum_stake() {
+    let unlimited = StakeLimits::default();
+    assert_eq!(
+        code(unlimited.check_amount(U256::zero())),
+        Err(Error::BelowMinStake as u16)
+    );
+    assert_eq!(code(unlimited.check_amount(U256::one())), Ok(()));
+
+    let limits = StakeLimits {
+        min_stake: U256::from(10u64),
+        max_stake_per_address: None,
+    };
+    assert_eq!(
+        code(limits.check_amount(U256::from(9u64))),
+        Err(Error::BelowMinStake as u16)
+    );
+    assert_eq!(code(limits.check_amount(U256::from(10u64))), Ok(()));
+}
+
+#[test]
+fn test_check_cap_prefers_the_cap_of_the_address() {
+    let unlimited = StakeLimits::default();
+    assert_eq!(
+        code(unlimited.check_cap(U256::MAX, U256::zero(), None)),
+        Ok(())
+    );
+
+    let limits = StakeLimits {
+        min_stake: U256::zero(),
+        max_stake_per_address: Some(U256::from(100u64)),
+    };
+    assert_eq!(
+        code(limits.check_cap(U256::from(40u64), U256::from(60u64), None)),
+        Ok(())
+    );
+    assert_eq!(
+        code(limits.check_cap(U256::from(41u64), U256::from(60u64), None)),
+        Err(Error::StakeCapExceeded as u16)
+    );
+    // An address cap overrides the pool-wide maximum, both ways.
+    let raised = Some(U256::from(150u64));
+    assert_eq!(
+        code(limits.check_cap(U256::from(90u64), U256::from(60u64), raised)),
+        Ok(())
+    );
+    let lowered = Some(U256::from(50u64));
+    assert_eq!(
+        code(limits.check_cap(U256::from(1u64), U256::from(50u64), lowered)),
+        Err(Error::StakeCapExceeded as u16)
+    );
+    assert_eq!(
+        code(unlimited.check_cap(U256::from(1u64), U256::from(50u64), lowered)),
+        Err(Error::StakeCapExceeded as u16)
+    );
+}
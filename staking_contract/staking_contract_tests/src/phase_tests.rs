@@ -0,0 +1,127 @@
+//! Tests of the pool lifecycle of `staking_contract::phase`.
+use staking_contract::{
+    phase::{Operation, PoolPhase},
+    reward::RewardMode,
+    schedule::Schedule,
+};
+
+const SCHEDULE: Schedule = Schedule {
+    staking_starts: 1_000,
+    staking_ends: 2_000,
+    withdraw_starts: 3_000,
+    withdraw_ends: 4_000,
+};
+
+const OPERATIONS: [Operation; 16] = [
+    Operation::Stake,
+    Operation::Withdraw,
+    Operation::ClaimRewards,
+    Operation::AddReward,
+    Operation::Compound,
+    Operation::TransferStake,
+    Operation::SweepDust,
+    Operation::Pause,
+    Operation::Unpause,
+    Operation::EmergencyWithdraw,
+    Operation::Reschedule,
+    Operation::ConfigureStaking,
+    Operation::SetReferralRate,
+    Operation::ManageOperators,
+    Operation::ManageRoles,
+    Operation::Migrate,
+];
+
+#[test]
+fn test_phase_follows_the_schedule_unless_paused() {
+    let cases = [
+        (0, PoolPhase::Pending),
+        (999, PoolPhase::Pending),
+        (1_000, PoolPhase::Staking),
+        (1_999, PoolPhase::Staking),
+        (2_000, PoolPhase::Locked),
+        (3_000, PoolPhase::WithdrawWindow),
+        (3_999, PoolPhase::WithdrawWindow),
+        (4_000, PoolPhase::Closed),
+        (u64::MAX, PoolPhase::Closed),
+    ];
+    for (now, phase) in cases {
+        assert_eq!(PoolPhase::at(now, &SCHEDULE, false), phase, "at {}", now);
+        assert_eq!(PoolPhase::at(now, &SCHEDULE, true), PoolPhase::Paused);
+    }
+}
+
+#[test]
+fn test_allowed_operations_per_phase() {
+    use PoolPhase::*;
+    // Rows follow `OPERATIONS`, columns Pending, Staking, Locked, WithdrawWindow, Closed, Paused.
+    let pro_rata = [
+        [false, true, false, false, false, false],
+        [false, false, false, true, true, false],
+        [false, false, false, true, true, false],
+        [true, true, true, false, false, false],
+        [true, true, true, true, true, false],
+        [true, true, true, true, true, false],
+        [false, false, true, true, true, false],
+        [true, true, true, true, true, false],
+        [false, false, false, false, false, true],
+        [false, false, false, false, false, true],
+        [true, true, true, true, false, false],
+        [true, true, false, false, false, false],
+        [true, true, true, true, true, false],
+        [true, true, true, true, true, true],
+        [true, true, true, true, true, true],
+        [true, true, true, true, true, true],
+    ];
+    let mut streaming = pro_rata;
+    streaming[1] = [false, true, true, true, true, false];
+    streaming[2] = [false, true, true, true, true, false];
+
+    let phases = [Pending, Staking, Locked, WithdrawWindow, Closed, Paused];
+    for (reward_mode, table) in [
+        (RewardMode::ProRata, pro_rata),
+        (RewardMode::FixedPoint, pro_rata),
+        (RewardMode::Streaming, streaming),
+        (RewardMode::Vault, streaming),
+    ] {
+        for (operation, row) in OPERATIONS.iter().zip(table.iter()) {
+            for (phase, allowed) in phases.iter().zip(row.iter()) {
+                assert_eq!(
+                    operation.is_allowed(*phase, reward_mode),
+                    *allowed,
+                    "{:?} in {:?} with {:?}",
+                    operation,
+                    phase,
+                    reward_mode
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_only_pause_recovery_and_administration_are_allowed_while_paused() {
+    for reward_mode in [
+        RewardMode::ProRata,
+        RewardMode::Streaming,
+        RewardMode::FixedPoint,
+        RewardMode::Vault,
+    ] {
+        for operation in OPERATIONS {
+            let recovers = matches!(operation, Operation::Unpause | Operation::EmergencyWithdraw);
+            let administers = matches!(
+                operation,
+                Operation::ManageOperators | Operation::ManageRoles | Operation::Migrate
+            );
+            assert_eq!(
+                operation.is_allowed(PoolPhase::Paused, reward_mode),
+                recovers || administers
+            );
+            // Recovery operations are never allowed by the schedule alone.
+            if recovers {
+                assert!(PoolPhase::SCHEDULED
+                    .iter()
+                    .all(|phase| !operation.is_allowed(*phase, reward_mode)));
+            }
+        }
+    }
+}
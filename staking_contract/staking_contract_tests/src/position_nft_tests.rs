@@ -0,0 +1,36 @@
+//! Tests of the metadata of position NFTs and of the token identifiers returned by CEP-78.
+use casper_types::U256;
+use staking_contract::{
+    error::Error,
+    lock::Position,
+    position_nft::{metadata, parse_token_id},
+};
+
+#[test]
+fn test_position_metadata_carries_amount_tier_and_unlock_time() {
+    let position = Position {
+        id: 3,
+        amount: U256::from(1_000_000_000u64) * U256::from(1_000_000_000u64),
+        timestamp: 100,
+        lock_tier: 2,
+        unlock_time: 86_400_100,
+    };
+    assert_eq!(
+        metadata(&position),
+        r#"{"amount":"1000000000000000000","lock_tier":"2","unlock_time":"86400100"}"#
+    );
+}
+
+#[test]
+fn test_parse_ordinal_token_ids() {
+    assert_eq!(parse_token_id("0").map_err(|e| e as u16), Ok(0));
+    assert_eq!(parse_token_id("42").map_err(|e| e as u16), Ok(42));
+    assert_eq!(
+        parse_token_id("7a9f").map_err(|e| e as u16),
+        Err(Error::InvalidPositionNft as u16)
+    );
+    assert_eq!(
+        parse_token_id("").map_err(|e| e as u16),
+        Err(Error::InvalidPositionNft as u16)
+    );
+}
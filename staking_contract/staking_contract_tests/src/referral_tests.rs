@@ -0,0 +1,21 @@
+//! Tests of the referral shares of `staking_contract::referral`.
+use casper_types::U256;
+use staking_contract::{
+    error::Error,
+    referral::{referral_share, REFERRAL_BASIS_POINTS},
+};
+
+#[test]
+fn test_referral_share_rounds_down_and_rejects_rates_over_100_percent() {
+    let share = |reward: u64, rate: u64| {
+        referral_share(U256::from(reward), rate).map_err(|error| error as u16)
+    };
+    assert_eq!(share(10, 2_000), Ok(U256::from(2u64)));
+    assert_eq!(share(10, 2_500), Ok(U256::from(2u64)));
+    assert_eq!(share(10, 0), Ok(U256::zero()));
+    assert_eq!(share(10, REFERRAL_BASIS_POINTS), Ok(U256::from(10u64)));
+    assert_eq!(
+        share(10, REFERRAL_BASIS_POINTS + 1),
+        Err(Error::InvalidReferralRate as u16)
+    );
+}
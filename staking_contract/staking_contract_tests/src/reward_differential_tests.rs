@@ -0,0 +1,232 @@
+//! Checks the pro-rata reward formulas of `staking_contract::reward` against a reference of the
+//! `Staking.sol` math, where every `SafeMath` revert is a `None`.
+//!
+//! The reference evaluates the Solidity statements on exact 512-bit integers and reverts wherever
+//! `SafeMath` does: when a subtraction would go below zero, a product does not fit in a 256-bit
+//! word, or a division is by zero.
+use casper_types::{U256, U512};
+use staking_contract::reward::{after_close_reward, early_withdraw_reward};
+
+use crate::rng::Rng;
+
+const CASES: usize = 10_000;
+
+fn word(value: U256) -> U512 {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+fn word_max() -> U512 {
+    (U512::one() << 256) - 1
+}
+
+/// `SafeMath.sub`: `require(b <= a)`.
+fn sub(a: U512, b: U512) -> Option<U512> {
+    if b <= a {
+        Some(a - b)
+    } else {
+        None
+    }
+}
+
+/// `SafeMath.mul`: `require(c / a == b)`, that is the exact product fits in a word.
+fn mul(a: U512, b: U512) -> Option<U512> {
+    let c = a * b;
+    if c <= word_max() {
+        Some(c)
+    } else {
+        None
+    }
+}
+
+/// `SafeMath.div`: `require(b > 0)`.
+fn div(a: U512, b: U512) -> Option<U512> {
+    if b.is_zero() {
+        None
+    } else {
+        Some(a / b)
+    }
+}
+
+/// `Staking.sol::_withdrawEarly`:
+///
+/// ```solidity
+/// uint256 denom = (withdrawEnds.sub(stakingEnds)).mul(stakedTotal);
+/// uint256 reward = (
+///   ( (block.timestamp.sub(stakingEnds)).mul(earlyWithdrawReward) ).mul(amount)
+/// ).div(denom);
+/// ```
+fn reference_early_withdraw_reward(
+    early_withdraw_reward: U512,
+    amount: U512,
+    staked_total: U512,
+    block_timestamp: U512,
+    staking_ends: U512,
+    withdraw_ends: U512,
+) -> Option<U512> {
+    let denom = mul(sub(withdraw_ends, staking_ends)?, staked_total)?;
+    let elapsed = sub(block_timestamp, staking_ends)?;
+    div(mul(mul(elapsed, early_withdraw_reward)?, amount)?, denom)
+}
+
+/// `Staking.sol::_withdrawAfterClose`:
+///
+/// ```solidity
+/// uint256 rewBal = rewardBalance;
+/// uint256 reward = (rewBal.mul(amount)).div(stakedBalance);
+/// ```
+fn reference_after_close_reward(
+    reward_balance: U512,
+    amount: U512,
+    staked_balance: U512,
+) -> Option<U512> {
+    div(mul(reward_balance, amount)?, staked_balance)
+}
+
+/// Random amount: a small one, one of up to 64 bits, or any `U256`.
+fn random_amount(rng: &mut Rng) -> U256 {
+    match rng.next() % 3 {
+        0 => U256::from(rng.below(32)),
+        1 => U256::from(rng.u64()),
+        _ => rng.u256(),
+    }
+}
+
+/// Random schedule `(staking_ends, withdraw_ends, now)`, mostly well formed, sometimes with the
+/// withdraw window closing before `staking_ends`, `now` falling outside of it, or any times at all.
+fn schedule(rng: &mut Rng) -> (u64, u64, u64) {
+    if rng.next() % 8 == 0 {
+        return (rng.u64(), rng.u64(), rng.u64());
+    }
+    let staking_ends = rng.below(40);
+    let withdraw_ends = match rng.next() % 8 {
+        0 => rng.below(40),
+        1 => staking_ends,
+        _ => staking_ends + rng.below(32),
+    };
+    let now = match rng.next() % 8 {
+        0 => rng.below(40),
+        _ => staking_ends + rng.below(32) % (withdraw_ends.saturating_sub(staking_ends) + 1),
+    };
+    (staking_ends, withdraw_ends, now)
+}
+
+#[test]
+fn test_early_withdraw_reward_matches_staking_sol() {
+    let mut rng = Rng(0x5eed_0001);
+    let mut paid = 0;
+    for case in 0..CASES {
+        let (staking_ends, withdraw_ends, now) = schedule(&mut rng);
+        let early_withdraw_reward = random_amount(&mut rng);
+        let staked_total = random_amount(&mut rng);
+        let amount = if rng.next() % 2 == 0 {
+            random_amount(&mut rng).min(staked_total)
+        } else {
+            random_amount(&mut rng)
+        };
+
+        let expected = reference_early_withdraw_reward(
+            word(early_withdraw_reward),
+            word(amount),
+            word(staked_total),
+            U512::from(now),
+            U512::from(staking_ends),
+            U512::from(withdraw_ends),
+        );
+        let actual = early_withdraw_reward(
+            early_withdraw_reward,
+            amount,
+            staked_total,
+            now,
+            staking_ends,
+            withdraw_ends,
+        )
+        .ok();
+        assert_eq!(
+            actual.map(word),
+            expected,
+            "case {}: early_withdraw_reward {} amount {} staked_total {} now {} staking_ends {} \
+             withdraw_ends {}",
+            case,
+            early_withdraw_reward,
+            amount,
+            staked_total,
+            now,
+            staking_ends,
+            withdraw_ends
+        );
+        paid += actual.is_some() as usize;
+    }
+    // Most random words overflow, but enough cases have to go through to compare results.
+    assert!(
+        paid > CASES / 20,
+        "only {} of {} cases paid out",
+        paid,
+        CASES
+    );
+}
+
+#[test]
+fn test_after_close_reward_matches_staking_sol() {
+    let mut rng = Rng(0x5eed_0002);
+    let mut paid = 0;
+    for case in 0..CASES {
+        let reward_balance = random_amount(&mut rng);
+        let staked_balance = random_amount(&mut rng);
+        let amount = if rng.next() % 2 == 0 {
+            random_amount(&mut rng).min(staked_balance)
+        } else {
+            random_amount(&mut rng)
+        };
+
+        let expected =
+            reference_after_close_reward(word(reward_balance), word(amount), word(staked_balance));
+        let actual = after_close_reward(reward_balance, amount, staked_balance).ok();
+        assert_eq!(
+            actual.map(word),
+            expected,
+            "case {}: reward_balance {} amount {} staked_balance {}",
+            case,
+            reward_balance,
+            amount,
+            staked_balance
+        );
+        paid += actual.is_some() as usize;
+    }
+    assert!(
+        paid > CASES / 20,
+        "only {} of {} cases paid out",
+        paid,
+        CASES
+    );
+}
+
+#[test]
+fn test_early_withdraw_reward_reverts_like_safe_math() {
+    let reward = |now, staking_ends, withdraw_ends, staked_total| {
+        early_withdraw_reward(
+            U256::from(100u64),
+            U256::from(10u64),
+            U256::from(staked_total),
+            now,
+            staking_ends,
+            withdraw_ends,
+        )
+        .ok()
+    };
+    // Grows linearly from zero at `staking_ends` to the whole share at `withdraw_ends`.
+    assert_eq!(reward(100, 100, 200, 50u64), Some(U256::zero()));
+    assert_eq!(reward(150, 100, 200, 50u64), Some(U256::from(10u64)));
+    assert_eq!(reward(200, 100, 200, 50u64), Some(U256::from(20u64)));
+    // `now - stakingEnds` underflows before `staking_ends`.
+    assert_eq!(reward(99, 100, 200, 50u64), None);
+    // `withdrawEnds - stakingEnds` underflows when the withdraw window closes first.
+    assert_eq!(reward(150, 100, 99, 50u64), None);
+    // Division by zero when nothing was staked or the window is empty.
+    assert_eq!(reward(150, 100, 200, 0u64), None);
+    assert_eq!(reward(100, 100, 100, 50u64), None);
+    // Multiplication overflow.
+    assert!(early_withdraw_reward(U256::MAX, U256::from(2u64), U256::one(), 1, 0, 1).is_err());
+    assert!(after_close_reward(U256::MAX, U256::from(2u64), U256::one()).is_err());
+}
@@ -0,0 +1,47 @@
+//! Random inputs for the property tests.
+use casper_types::U256;
+
+/// Deterministic splitmix64 generator, so that failures can be replayed.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Value below `2^bits`, zero about one time in sixteen.
+    pub fn below(&mut self, bits: u32) -> u64 {
+        if self.next() % 16 == 0 {
+            0
+        } else {
+            self.next() >> (64 - bits)
+        }
+    }
+
+    /// `U256` of random bit length, hitting `0`, `1` and `U256::MAX` every now and then.
+    pub fn u256(&mut self) -> U256 {
+        match self.next() % 16 {
+            0 => U256::zero(),
+            1 => U256::one(),
+            2 => U256::MAX,
+            _ => {
+                let words = [self.next(), self.next(), self.next(), self.next()];
+                U256(words) >> (self.next() % 256) as usize
+            }
+        }
+    }
+
+    /// `u64` of random bit length, hitting `0`, `1` and `u64::MAX` every now and then.
+    pub fn u64(&mut self) -> u64 {
+        match self.next() % 16 {
+            0 => 0,
+            1 => 1,
+            2 => u64::MAX,
+            _ => self.next() >> (self.next() % 64),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Property tests of `staking_contract::safe_math` and of the accounting built on it, checked
+//! against arithmetic carried out in a wider type.
+use casper_types::{U256, U512};
+use staking_contract::{
+    error::Error,
+    lock::LockTier,
+    penalty::{early_withdraw_penalty, PENALTY_BASIS_POINTS},
+    safe_math::{mul_div, SafeMath},
+};
+use std::convert::TryFrom;
+
+use crate::rng::Rng;
+
+const CASES: usize = 10_000;
+
+fn code<T>(result: Result<T, Error>) -> Result<T, u16> {
+    result.map_err(|error| error as u16)
+}
+
+fn widen(value: U256) -> U512 {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+/// `value` if it fits in a `U256`, [`Error::Overflow`] otherwise.
+fn narrow(value: U512) -> Result<U256, u16> {
+    if value > widen(U256::MAX) {
+        return Err(Error::Overflow as u16);
+    }
+    let mut bytes = [0u8; 64];
+    value.to_little_endian(&mut bytes);
+    Ok(U256::from_little_endian(&bytes[..32]))
+}
+
+#[test]
+fn test_u256_safe_math_matches_wide_arithmetic() {
+    let mut rng = Rng(0x5eed_0003);
+    for _ in 0..CASES {
+        let (a, b) = (rng.u256(), rng.u256());
+        let (wide_a, wide_b) = (widen(a), widen(b));
+
+        assert_eq!(
+            code(a.safe_add(b)),
+            narrow(wide_a + wide_b),
+            "{} + {}",
+            a,
+            b
+        );
+        assert_eq!(
+            code(a.safe_mul(b)),
+            narrow(wide_a * wide_b),
+            "{} * {}",
+            a,
+            b
+        );
+        let difference = if a < b {
+            Err(Error::Underflow as u16)
+        } else {
+            narrow(wide_a - wide_b)
+        };
+        assert_eq!(code(a.safe_sub(b)), difference, "{} - {}", a, b);
+        let quotient = if b.is_zero() {
+            Err(Error::DivisionByZero as u16)
+        } else {
+            narrow(wide_a / wide_b)
+        };
+        assert_eq!(code(a.safe_div(b)), quotient, "{} / {}", a, b);
+    }
+}
+
+#[test]
+fn test_u64_safe_math_matches_wide_arithmetic() {
+    let mut rng = Rng(0x5eed_0004);
+    let narrow = |value: u128| u64::try_from(value).map_err(|_| Error::Overflow as u16);
+    for _ in 0..CASES {
+        let (a, b) = (rng.u64(), rng.u64());
+        let (wide_a, wide_b) = (u128::from(a), u128::from(b));
+
+        assert_eq!(
+            code(a.safe_add(b)),
+            narrow(wide_a + wide_b),
+            "{} + {}",
+            a,
+            b
+        );
+        assert_eq!(
+            code(a.safe_mul(b)),
+            narrow(wide_a * wide_b),
+            "{} * {}",
+            a,
+            b
+        );
+        let difference = a.checked_sub(b).ok_or(Error::Underflow as u16);
+        assert_eq!(code(a.safe_sub(b)), difference, "{} - {}", a, b);
+        let quotient = a.checked_div(b).ok_or(Error::DivisionByZero as u16);
+        assert_eq!(code(a.safe_div(b)), quotient, "{} / {}", a, b);
+    }
+}
+
+#[test]
+fn test_mul_div_fails_on_intermediate_overflow_before_dividing() {
+    let mut rng = Rng(0x5eed_0005);
+    for _ in 0..CASES {
+        let (a, b, c) = (rng.u256(), rng.u256(), rng.u256());
+        let product = widen(a) * widen(b);
+        let expected = if product > widen(U256::MAX) {
+            Err(Error::Overflow as u16)
+        } else if c.is_zero() {
+            Err(Error::DivisionByZero as u16)
+        } else {
+            narrow(product / widen(c))
+        };
+        assert_eq!(code(mul_div(a, b, c)), expected, "{} * {} / {}", a, b, c);
+    }
+}
+
+#[test]
+fn test_safe_math_edge_cases() {
+    assert_eq!(
+        code(U256::MAX.safe_add(U256::one())),
+        Err(Error::Overflow as u16)
+    );
+    assert_eq!(
+        code(U256::zero().safe_sub(U256::one())),
+        Err(Error::Underflow as u16)
+    );
+    assert_eq!(
+        code(U256::MAX.safe_mul(U256::from(2u64))),
+        Err(Error::Overflow as u16)
+    );
+    assert_eq!(
+        code(U256::one().safe_div(U256::zero())),
+        Err(Error::DivisionByZero as u16)
+    );
+    assert_eq!(code(U256::MAX.safe_sub(U256::MAX)), Ok(U256::zero()));
+    assert_eq!(code(U256::MAX.safe_div(U256::one())), Ok(U256::MAX));
+    assert_eq!(code(u64::MAX.safe_add(1)), Err(Error::Overflow as u16));
+    assert_eq!(code(0u64.safe_sub(1)), Err(Error::Underflow as u16));
+    assert_eq!(code(0u64.safe_div(0)), Err(Error::DivisionByZero as u16));
+    // The weight of a huge stake overflows instead of wrapping around.
+    let tier = LockTier {
+        duration: 0,
+        multiplier: 20_000,
+    };
+    assert_eq!(code(tier.weight(U256::MAX)), Err(Error::Overflow as u16));
+    assert_eq!(
+        code(tier.weight(U256::MAX / 2)),
+        Err(Error::Overflow as u16)
+    );
+    assert_eq!(code(tier.weight(U256::from(5u64))), Ok(U256::from(10u64)));
+}
+
+#[test]
+fn test_early_withdraw_penalty_never_exceeds_amount_and_decays() {
+    let mut rng = Rng(0x5eed_0006);
+    for _ in 0..CASES {
+        let amount = U256::from(rng.below(64)) * U256::from(rng.below(64));
+        let rate = rng.next() % (PENALTY_BASIS_POINTS + 1);
+        let staking_ends = rng.below(40);
+        let withdraw_ends = staking_ends + rng.below(32);
+        let now = staking_ends + rng.below(32);
+        let later = now + rng.below(32);
+
+        let penalty = early_withdraw_penalty(amount, rate, now, staking_ends, withdraw_ends)
+            .expect("penalty of a bounded amount");
+        let later_penalty =
+            early_withdraw_penalty(amount, rate, later, staking_ends, withdraw_ends)
+                .expect("penalty of a bounded amount");
+        assert!(penalty <= amount);
+        assert!(later_penalty <= penalty);
+        if now >= withdraw_ends {
+            assert!(penalty.is_zero());
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Tests of the schedule validation of `staking_contract::schedule`.
+use staking_contract::{error::Error, schedule::Schedule};
+
+const SCHEDULE: Schedule = Schedule {
+    staking_starts: 1_000,
+    staking_ends: 2_000,
+    withdraw_starts: 3_000,
+    withdraw_ends: 4_000,
+};
+
+fn code<T>(result: Result<T, Error>) -> Result<T, u16> {
+    result.map_err(|error| error as u16)
+}
+
+#[test]
+fn test_validate_accepts_ordered_windows_and_clamps_a_past_start() {
+    assert_eq!(code(SCHEDULE.validate(0)), Ok(SCHEDULE));
+    assert_eq!(
+        code(SCHEDULE.validate(1_500)),
+        Ok(Schedule {
+            staking_starts: 1_500,
+            ..SCHEDULE
+        })
+    );
+    // Withdrawals may open as soon as staking ends.
+    let back_to_back = Schedule {
+        withdraw_starts: 2_000,
+        ..SCHEDULE
+    };
+    assert_eq!(code(back_to_back.validate(0)), Ok(back_to_back));
+}
+
+#[test]
+fn test_validate_rejects_inconsistent_windows() {
+    let cases = [
+        (
+            Schedule {
+                staking_ends: 1_000,
+                ..SCHEDULE
+            },
+            0,
+            Error::InvalidStakingEnds,
+        ),
+        // A start clamped to the block time can pass the end of staking.
+        (SCHEDULE, 2_000, Error::InvalidStakingEnds),
+        (
+            Schedule {
+                withdraw_starts: 1_999,
+                ..SCHEDULE
+            },
+            0,
+            Error::InvalidWithdrawStarts,
+        ),
+        (
+            Schedule {
+                withdraw_ends: 3_000,
+                ..SCHEDULE
+            },
+            0,
+            Error::InvalidWithdrawEnds,
+        ),
+    ];
+    for (schedule, now, error) in cases {
+        assert_eq!(
+            code(schedule.validate(now)),
+            Err(error as u16),
+            "{:?}",
+            schedule
+        );
+    }
+}
+
+#[test]
+fn test_reschedule_only_extends_windows_not_yet_started() {
+    let extended = Schedule {
+        staking_starts: 500,
+        staking_ends: 2_500,
+        withdraw_starts: 3_000,
+        withdraw_ends: 5_000,
+    };
+    assert_eq!(code(SCHEDULE.reschedule(extended, 0)), Ok(extended));
+
+    let shortened = Schedule {
+        staking_ends: 1_500,
+        ..SCHEDULE
+    };
+    assert_eq!(
+        code(SCHEDULE.reschedule(shortened, 0)),
+        Err(Error::ScheduleShortened as u16)
+    );
+    let delayed = Schedule {
+        withdraw_starts: 3_500,
+        ..SCHEDULE
+    };
+    assert_eq!(
+        code(SCHEDULE.reschedule(delayed, 0)),
+        Err(Error::ScheduleShortened as u16)
+    );
+
+    // Once staking started, only the withdrawal window can still change.
+    let later_withdrawals = Schedule {
+        withdraw_ends: 5_000,
+        ..SCHEDULE
+    };
+    assert_eq!(
+        code(SCHEDULE.reschedule(later_withdrawals, 1_500)),
+        Ok(later_withdrawals)
+    );
+    let longer_staking = Schedule {
+        staking_ends: 2_500,
+        ..later_withdrawals
+    };
+    assert_eq!(
+        code(SCHEDULE.reschedule(longer_staking, 1_500)),
+        Err(Error::WindowStarted as u16)
+    );
+    assert_eq!(
+        code(SCHEDULE.reschedule(later_withdrawals, 3_000)),
+        Err(Error::WindowStarted as u16)
+    );
+}
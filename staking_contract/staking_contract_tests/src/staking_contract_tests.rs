@@ -4,13 +4,18 @@ use blake2::{
 };
 use casper_engine_test_support::{
     ExecuteRequestBuilder, InMemoryWasmTestBuilder, WasmTestBuilder, DEFAULT_ACCOUNT_ADDR,
-    DEFAULT_RUN_GENESIS_REQUEST,
+    DEFAULT_RUN_GENESIS_REQUEST, MINIMUM_ACCOUNT_CREATION_BALANCE,
 };
 use casper_execution_engine::storage::global_state::in_memory::InMemoryGlobalState;
 use casper_types::{
-    bytesrepr::ToBytes, runtime_args, ContractHash, ContractPackageHash, Key, RuntimeArgs,
-    BLAKE2B_DIGEST_LENGTH, U256,
+    account::AccountHash,
+    bytesrepr::{FromBytes, ToBytes},
+    crypto, runtime_args,
+    system::mint,
+    CLType, ContractHash, ContractPackageHash, Key, PublicKey, RuntimeArgs, SecretKey, Signature,
+    BLAKE2B_DIGEST_LENGTH, U256, U512,
 };
+use staking_contract::{allowlist::MerkleTree, permit::stake_terms_preimage};
 
 const ADDRESS: &str = "9e7283533626d0c7d43fa9ca745af20d8dac7fc3bfe03cdfe50d523a2a0f498d";
 
@@ -21,6 +26,11 @@ const ERC20_CONTRACT_PACKAGE_HASH: &str = "erc20-contract_package_hash";
 const STAKING_CONTRACT_HASH: &str = "staking_contract_hash";
 const STAKING_CONTRACT_PACKAGE_HASH: &str = "contract_package_hash";
 const ALLOWANCES_SEED_UREF: &str = "allowances";
+const FACTORY_WASM: &str = "staking_factory.wasm";
+const FACTORY_CONTRACT_HASH: &str = "staking_factory_contract_hash";
+const FACTORY_CONTRACT_PACKAGE_HASH: &str = "staking_factory_package_hash";
+const CEP78_WASM: &str = "cep78.wasm";
+const POSITION_NFT_COLLECTION_NAME: &str = "ferrum_positions";
 
 #[test]
 fn test_approve_and_stake() {
@@ -74,10 +84,18 @@ fn test_approve_and_stake() {
         "address" => "9e7283533626d0c7d43fa9ca745af20d8dac7fc3bfe03cdfe50d523a2a0f498d".to_string(),
         "staking_starts" => 0u64,
         "staking_ends" => 1781708875776u64,
-        "withdraw_starts" => 0u64,
-        "withdraw_ends" => 1781708875776u64,
+        "withdraw_starts" => 1781708875776u64,
+        "withdraw_ends" => 1781708975776u64,
         "staking_total" => U256::from(500000i64),
         "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
     };
 
     let staking_contract_install_request = ExecuteRequestBuilder::standard(
@@ -125,6 +143,7 @@ fn test_approve_and_stake() {
     let stake_args = runtime_args! {
         "amount" => U256::from(5i64),
         "staking_contract_package_hash" => get_stacking_contract_package_hash(&builder).to_formatted_string(),
+        "lock_tier" => 0u8,
     };
 
     let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
@@ -190,10 +209,18 @@ fn test_approve_and_stake_and_amount_staked() {
         "address" => "9e7283533626d0c7d43fa9ca745af20d8dac7fc3bfe03cdfe50d523a2a0f498d".to_string(),
         "staking_starts" => 0u64,
         "staking_ends" => 1781708875776u64,
-        "withdraw_starts" => 0u64,
-        "withdraw_ends" => 1781708875776u64,
+        "withdraw_starts" => 1781708875776u64,
+        "withdraw_ends" => 1781708975776u64,
         "staking_total" => U256::from(500000i64),
         "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
     };
 
     let staking_contract_install_request = ExecuteRequestBuilder::standard(
@@ -243,6 +270,7 @@ fn test_approve_and_stake_and_amount_staked() {
     let stake_args = runtime_args! {
         "amount" => U256::from(5i64),
         "staking_contract_package_hash" => get_stacking_contract_package_hash(&builder).to_formatted_string(),
+        "lock_tier" => 0u8,
     };
 
     let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
@@ -325,10 +353,18 @@ fn test_approve_and_stake_and_staker_reward() {
         "address" => "9e7283533626d0c7d43fa9ca745af20d8dac7fc3bfe03cdfe50d523a2a0f498d".to_string(),
         "staking_starts" => 0u64,
         "staking_ends" => 1781708875776u64,
-        "withdraw_starts" => 0u64,
-        "withdraw_ends" => 1781708875776u64,
+        "withdraw_starts" => 1781708875776u64,
+        "withdraw_ends" => 1781708975776u64,
         "staking_total" => U256::from(500000i64),
         "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
     };
 
     let staking_contract_install_request = ExecuteRequestBuilder::standard(
@@ -378,6 +414,7 @@ fn test_approve_and_stake_and_staker_reward() {
     let stake_args = runtime_args! {
         "amount" => U256::from(5i64),
         "staking_contract_package_hash" => get_stacking_contract_package_hash(&builder).to_formatted_string(),
+        "lock_tier" => 0u8,
     };
 
     let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
@@ -478,10 +515,18 @@ fn test_approve_and_stake_and_get_current_reward() {
         "address" => "9e7283533626d0c7d43fa9ca745af20d8dac7fc3bfe03cdfe50d523a2a0f498d".to_string(),
         "staking_starts" => 0u64,
         "staking_ends" => 1781708875776u64,
-        "withdraw_starts" => 0u64,
-        "withdraw_ends" => 1781708875776u64,
+        "withdraw_starts" => 1781708875776u64,
+        "withdraw_ends" => 1781708975776u64,
         "staking_total" => U256::from(500000i64),
         "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
     };
 
     let staking_contract_install_request = ExecuteRequestBuilder::standard(
@@ -531,6 +576,7 @@ fn test_approve_and_stake_and_get_current_reward() {
     let stake_args = runtime_args! {
         "amount" => U256::from(5i64),
         "staking_contract_package_hash" => get_stacking_contract_package_hash(&builder).to_formatted_string(),
+        "lock_tier" => 0u8,
     };
 
     let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
@@ -626,11 +672,19 @@ fn test_approve_and_stake_and_withdraw() {
         "name" => "FerrumX".to_string(),
         "address" => "9e7283533626d0c7d43fa9ca745af20d8dac7fc3bfe03cdfe50d523a2a0f498d".to_string(),
         "staking_starts" => 0u64,
-        "staking_ends" => 1781708875776u64,
-        "withdraw_starts" => 0u64,
-        "withdraw_ends" => 1781708875776u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
         "staking_total" => U256::from(500000i64),
         "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
     };
 
     let staking_contract_install_request = ExecuteRequestBuilder::standard(
@@ -680,6 +734,7 @@ fn test_approve_and_stake_and_withdraw() {
     let stake_args = runtime_args! {
         "amount" => U256::from(5i64),
         "staking_contract_package_hash" => get_stacking_contract_package_hash(&builder).to_formatted_string(),
+        "lock_tier" => 0u8,
     };
 
     let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
@@ -702,6 +757,7 @@ fn test_approve_and_stake_and_withdraw() {
         "withdraw",
         withdraw_args,
     )
+    .with_block_time(150_000)
     .build();
 
     builder.exec(withdraw_request).expect_success().commit();
@@ -758,11 +814,19 @@ fn test_approve_and_stake_and_add_reward() {
         "name" => "FerrumX".to_string(),
         "address" => "9e7283533626d0c7d43fa9ca745af20d8dac7fc3bfe03cdfe50d523a2a0f498d".to_string(),
         "staking_starts" => 0u64,
-        "staking_ends" => 1781708875776u64,
-        "withdraw_starts" => 0u64,
-        "withdraw_ends" => 1781708875776u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
         "staking_total" => U256::from(500000i64),
         "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
     };
 
     let staking_contract_install_request = ExecuteRequestBuilder::standard(
@@ -812,6 +876,7 @@ fn test_approve_and_stake_and_add_reward() {
     let stake_args = runtime_args! {
         "amount" => U256::from(5i64),
         "staking_contract_package_hash" => get_stacking_contract_package_hash(&builder).to_formatted_string(),
+        "lock_tier" => 0u8,
     };
 
     let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
@@ -840,52 +905,4248 @@ fn test_approve_and_stake_and_add_reward() {
     builder.exec(add_reward_request).expect_success().commit();
 }
 
-/// Creates a dictionary item key for an (owner, spender) pair.
-fn make_allowances_dictionary_item_key(owner: Key, spender: Key) -> String {
-    let mut preimage = Vec::new();
-    preimage.append(&mut owner.to_bytes().unwrap());
-    preimage.append(&mut spender.to_bytes().unwrap());
+#[test]
+fn test_streaming_stake_claim_rewards_and_withdraw() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
 
-    let key_bytes = create_blake2b_hash(&preimage);
-    hex::encode(&key_bytes)
-}
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
 
-pub(crate) fn create_blake2b_hash<T: AsRef<[u8]>>(data: T) -> [u8; BLAKE2B_DIGEST_LENGTH] {
-    let mut result = [0; BLAKE2B_DIGEST_LENGTH];
-    // NOTE: Assumed safe as `BLAKE2B_DIGEST_LENGTH` is a valid value for a hasher
-    let mut hasher = VarBlake2b::new(BLAKE2B_DIGEST_LENGTH).expect("should create hasher");
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
 
-    hasher.update(data);
-    hasher.finalize_variable(|slice| {
-        result.copy_from_slice(slice);
-    });
-    result
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 1u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(5i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    // 20 tokens streamed over the 100 seconds of the staking window.
+    let add_reward_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "add_reward",
+        runtime_args! {
+            "reward_amount" => U256::from(20i64),
+            "withdrawable_amount" => U256::from(0i64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(add_reward_request).expect_success().commit();
+
+    let claim_rewards_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "claim_rewards",
+        runtime_args! {},
+    )
+    .with_block_time(50_000)
+    .build();
+
+    builder
+        .exec(claim_rewards_request)
+        .expect_success()
+        .commit();
+
+    let balance = balance_dictionary(
+        &builder,
+        erc20_contract_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(balance, U256::from(500000u64 - 5 - 20 + 10));
+
+    let withdraw_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "withdraw",
+        runtime_args! {
+            "amount" => U256::from(5u64),
+        },
+    )
+    .with_block_time(150_000)
+    .build();
+
+    builder.exec(withdraw_request).expect_success().commit();
+
+    let balance = balance_dictionary(
+        &builder,
+        erc20_contract_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(balance, U256::from(500000u64));
 }
 
-pub fn get_stacking_contract_package_hash(
-    builder: &WasmTestBuilder<InMemoryGlobalState>,
-) -> ContractPackageHash {
-    let erc20_hash_addr = builder
-        .get_expected_account(*DEFAULT_ACCOUNT_ADDR)
-        .named_keys()
-        .get(STAKING_CONTRACT_PACKAGE_HASH)
-        .expect("must have this entry in named keys")
-        .into_hash()
-        .expect("must get hash_addr");
+#[test]
+fn test_dual_token_rewards_are_paid_in_reward_token() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
 
-    ContractPackageHash::new(erc20_hash_addr)
+    // Both tokens are installed under the same named keys, so the reward token hashes are read
+    // before the staking token is installed.
+    let reward_token_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        ERC20_WASM,
+        runtime_args! {
+            "name" => "FERRUM_REWARD".to_string(),
+            "symbol" => "F_REWARD".to_string(),
+            "total_supply" => U256::from(500000i64),
+            "decimals" => 8u8,
+        },
+    )
+    .build();
+
+    builder
+        .exec(reward_token_install_request)
+        .expect_success()
+        .commit();
+
+    let reward_token_hash = get_erc20_contract_hash(&builder);
+    let reward_token_package_hash = get_erc20_contract_package_hash(&builder);
+    let reward_token_key: Key = reward_token_hash.into();
+
+    let erc_20_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        ERC20_WASM,
+        runtime_args! {
+            "name" => "FERRUM_ERC20".to_string(),
+            "symbol" => "F_ERC20".to_string(),
+            "total_supply" => U256::from(500000i64),
+            "decimals" => 8u8,
+        },
+    )
+    .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(reward_token_package_hash),
+        "reward_mode" => 1u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    for token_hash in [erc20_contract_hash, reward_token_hash] {
+        let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            token_hash,
+            "approve",
+            runtime_args! {
+                "spender" => staking_contract_key,
+                "amount" => U256::from(100i64),
+            },
+        )
+        .build();
+
+        builder.exec(approve_request).expect_success().commit();
+    }
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(5i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    let add_reward_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "add_reward",
+        runtime_args! {
+            "reward_amount" => U256::from(20i64),
+            "withdrawable_amount" => U256::from(0i64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(add_reward_request).expect_success().commit();
+
+    let staked_balance = balance_dictionary(
+        &builder,
+        erc20_contract_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(staked_balance, U256::from(500000u64 - 5));
+
+    let reward_balance = balance_dictionary(
+        &builder,
+        reward_token_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(reward_balance, U256::from(500000u64 - 20));
+
+    let claim_rewards_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "claim_rewards",
+        runtime_args! {},
+    )
+    .with_block_time(50_000)
+    .build();
+
+    builder
+        .exec(claim_rewards_request)
+        .expect_success()
+        .commit();
+
+    let reward_balance = balance_dictionary(
+        &builder,
+        reward_token_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(reward_balance, U256::from(500000u64 - 20 + 10));
+
+    let withdraw_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "withdraw",
+        runtime_args! {
+            "amount" => U256::from(5u64),
+        },
+    )
+    .with_block_time(150_000)
+    .build();
+
+    builder.exec(withdraw_request).expect_success().commit();
+
+    let staked_balance = balance_dictionary(
+        &builder,
+        erc20_contract_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(staked_balance, U256::from(500000u64));
+
+    let reward_balance = balance_dictionary(
+        &builder,
+        reward_token_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(reward_balance, U256::from(500000u64));
 }
 
-pub fn get_stacking_contract_hash(builder: &WasmTestBuilder<InMemoryGlobalState>) -> ContractHash {
-    let erc20_hash_addr = builder
-        .get_expected_account(*DEFAULT_ACCOUNT_ADDR)
-        .named_keys()
-        .get(STAKING_CONTRACT_HASH)
-        .expect("must have this entry in named keys")
-        .into_hash()
-        .expect("must get hash_addr");
+#[test]
+fn test_roles_guard_add_reward() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
 
-    ContractHash::new(erc20_hash_addr)
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+    let owner = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let add_reward_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "add_reward",
+            runtime_args! {
+                "reward_amount" => U256::from(10i64),
+                "withdrawable_amount" => U256::from(0i64),
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    // The installer holds every role, including the reward manager role.
+    builder.exec(add_reward_request()).expect_success().commit();
+
+    let renounce_role_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "renounce_role",
+        runtime_args! {
+            "role" => 1u8,
+        },
+    )
+    .build();
+
+    builder
+        .exec(renounce_role_request)
+        .expect_success()
+        .commit();
+
+    builder.exec(add_reward_request()).expect_failure();
+
+    let grant_role_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "grant_role",
+        runtime_args! {
+            "role" => 1u8,
+            "account" => owner,
+        },
+    )
+    .build();
+
+    builder.exec(grant_role_request).expect_success().commit();
+
+    builder.exec(add_reward_request()).expect_success().commit();
+
+    let renounce_owner_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "renounce_role",
+        runtime_args! {
+            "role" => 0u8,
+        },
+    )
+    .build();
+
+    builder
+        .exec(renounce_owner_request)
+        .expect_success()
+        .commit();
+
+    // Without the owner role the caller can no longer manage roles.
+    let revoke_role_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "revoke_role",
+        runtime_args! {
+            "role" => 1u8,
+            "account" => owner,
+        },
+    )
+    .build();
+
+    builder.exec(revoke_role_request).expect_failure();
+}
+
+#[test]
+fn test_pause_blocks_staking_and_allows_emergency_withdraw() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake",
+            runtime_args! {
+                "amount" => U256::from(5i64),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => 0u8,
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    builder.exec(stake_request()).expect_success().commit();
+
+    let add_reward_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "add_reward",
+        runtime_args! {
+            "reward_amount" => U256::from(20i64),
+            "withdrawable_amount" => U256::from(0i64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(add_reward_request).expect_success().commit();
+
+    let emergency_withdraw_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "emergency_withdraw",
+            runtime_args! {},
+        )
+        .with_block_time(50_000)
+        .build()
+    };
+
+    // Emergency withdrawals are only available while paused.
+    builder.exec(emergency_withdraw_request()).expect_failure();
+
+    let pause_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "pause",
+        runtime_args! {},
+    )
+    .build();
+
+    builder.exec(pause_request).expect_success().commit();
+
+    builder.exec(stake_request()).expect_failure();
+
+    let claim_rewards_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "claim_rewards",
+        runtime_args! {},
+    )
+    .with_block_time(50_000)
+    .build();
+
+    builder.exec(claim_rewards_request).expect_failure();
+
+    builder
+        .exec(emergency_withdraw_request())
+        .expect_success()
+        .commit();
+
+    // The principal is paid back while the reward stays in the pool.
+    let balance = balance_dictionary(
+        &builder,
+        erc20_contract_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(balance, U256::from(500000u64 - 20));
+
+    let unpause_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "unpause",
+        runtime_args! {},
+    )
+    .build();
+
+    builder.exec(unpause_request).expect_success().commit();
+
+    builder.exec(stake_request()).expect_success().commit();
+}
+
+#[test]
+fn test_upgrade_keeps_staker_state() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(5i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    // Only the upgrade session may migrate the storage.
+    let migrate_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "migrate",
+        runtime_args! {},
+    )
+    .build();
+
+    builder.exec(migrate_request).expect_failure();
+
+    // Deploying the contract again from the installing account with `upgrade` set upgrades the
+    // existing package.
+    let upgrade_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        runtime_args! {
+            "upgrade" => true,
+        },
+    )
+    .build();
+
+    builder.exec(upgrade_request).expect_success().commit();
+
+    assert_eq!(
+        get_stacking_contract_package_hash(&builder),
+        staking_contract_package_hash
+    );
+    let upgraded_contract_hash = get_stacking_contract_hash(&builder);
+    assert_ne!(upgraded_contract_hash, staking_contract_hash);
+
+    let amount_staked_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        upgraded_contract_hash,
+        "amount_staked",
+        runtime_args! {
+            "staker" => Key::from(*DEFAULT_ACCOUNT_ADDR),
+        },
+    )
+    .build();
+
+    builder
+        .exec(amount_staked_request)
+        .expect_success()
+        .commit();
+
+    let withdraw_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        upgraded_contract_hash,
+        "withdraw",
+        runtime_args! {
+            "amount" => U256::from(5u64),
+        },
+    )
+    .with_block_time(150_000)
+    .build();
+
+    builder.exec(withdraw_request).expect_success().commit();
+
+    let balance = balance_dictionary(
+        &builder,
+        erc20_contract_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(balance, U256::from(500000u64));
+}
+
+#[test]
+fn test_stake_emits_ces_event() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(5i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    let named_keys = builder
+        .query(None, Key::from(staking_contract_hash), &[])
+        .unwrap()
+        .as_contract()
+        .expect("must have staking contract")
+        .named_keys()
+        .clone();
+
+    // The three roles granted at install are followed by the stake.
+    let events_length: u32 = builder
+        .query(None, named_keys["__events_length"], &[])
+        .unwrap()
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone()
+        .into_t()
+        .expect("must convert to u32");
+    assert_eq!(events_length, 4);
+
+    let events_uref = *named_keys["__events"].as_uref().expect("must be a uref");
+    let event = builder
+        .query_dictionary_item(None, events_uref, "3")
+        .expect("should be stored value.")
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone();
+    assert_eq!(*event.cl_type(), CLType::Any);
+
+    let (event_name, bytes) = String::from_bytes(event.inner_bytes()).unwrap();
+    assert_eq!(event_name, "event_Stake");
+    let (token_address, bytes) = String::from_bytes(bytes).unwrap();
+    assert_eq!(token_address, ADDRESS);
+    let (staker_address, bytes) = Key::from_bytes(bytes).unwrap();
+    assert_eq!(staker_address, Key::Account(*DEFAULT_ACCOUNT_ADDR));
+    let (requested_amount, bytes) = U256::from_bytes(bytes).unwrap();
+    let (staked_amount, bytes) = U256::from_bytes(bytes).unwrap();
+    assert_eq!(requested_amount, U256::from(5u64));
+    assert_eq!(staked_amount, U256::from(5u64));
+    let (position_id, bytes) = u64::from_bytes(bytes).unwrap();
+    assert_eq!(position_id, 0);
+    let (lock_tier, bytes) = u8::from_bytes(bytes).unwrap();
+    let (unlock_time, bytes) = u64::from_bytes(bytes).unwrap();
+    assert_eq!(lock_tier, 0);
+    assert_eq!(unlock_time, 0);
+    let (beneficiary, bytes) = Key::from_bytes(bytes).unwrap();
+    assert_eq!(beneficiary, Key::Account(*DEFAULT_ACCOUNT_ADDR));
+    assert!(bytes.is_empty());
+}
+
+#[test]
+fn test_locked_stake_cannot_be_withdrawn_before_unlock() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    // Tier 1 locks until after the staking window and doubles the reward weight.
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => vec![(150_000u64, 20_000u64)],
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = |lock_tier: u8| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake",
+            runtime_args! {
+                "amount" => U256::from(5i64),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => lock_tier,
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    builder.exec(stake_request(1)).expect_success().commit();
+    builder.exec(stake_request(0)).expect_success().commit();
+    // There is no tier 2.
+    builder.exec(stake_request(2)).expect_failure();
+
+    let withdraw_request = |amount: u64, block_time: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "withdraw",
+            runtime_args! {
+                "amount" => U256::from(amount),
+            },
+        )
+        .with_block_time(block_time)
+        .build()
+    };
+
+    // Only the unlocked position can be withdrawn before the lock expires.
+    builder.exec(withdraw_request(10, 120_000)).expect_failure();
+    builder
+        .exec(withdraw_request(5, 120_000))
+        .expect_success()
+        .commit();
+    builder.exec(withdraw_request(5, 140_000)).expect_failure();
+    builder
+        .exec(withdraw_request(5, 160_000))
+        .expect_success()
+        .commit();
+
+    let balance = balance_dictionary(
+        &builder,
+        erc20_contract_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(balance, U256::from(500000u64));
+}
+
+#[test]
+fn test_withdraw_from_a_specific_position() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+    let staker = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    for (amount, block_time) in [(5u64, 0u64), (7, 1_000)] {
+        let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake",
+            runtime_args! {
+                "amount" => U256::from(amount),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => 0u8,
+            },
+        )
+        .with_block_time(block_time)
+        .build();
+
+        builder.exec(stake_request).expect_success().commit();
+    }
+
+    let withdraw_position_request = |position_id: u64, amount: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "withdraw_position",
+            runtime_args! {
+                "position_id" => position_id,
+                "amount" => U256::from(amount),
+            },
+        )
+        .with_block_time(120_000)
+        .build()
+    };
+
+    // Position 1 is left untouched even though position 0 is older.
+    builder
+        .exec(withdraw_position_request(1, 4))
+        .expect_success()
+        .commit();
+    // More than the position holds, and a position that was never opened.
+    builder
+        .exec(withdraw_position_request(1, 4))
+        .expect_failure();
+    builder
+        .exec(withdraw_position_request(2, 1))
+        .expect_failure();
+
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, staker, 0),
+        (0, U256::from(5u64), 0, 0, 0)
+    );
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, staker, 1),
+        (1, U256::from(3u64), 1_000, 0, 1_000)
+    );
+    assert_eq!(
+        live_positions_dictionary(&builder, staking_contract_hash, staker),
+        vec![0, 1]
+    );
+
+    // Emptied positions are dropped from the live positions, which `positions` and `withdraw` read.
+    builder
+        .exec(withdraw_position_request(1, 3))
+        .expect_success()
+        .commit();
+    assert_eq!(
+        live_positions_dictionary(&builder, staking_contract_hash, staker),
+        vec![0]
+    );
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, staker, 1),
+        (1, U256::zero(), 1_000, 0, 1_000)
+    );
+    builder
+        .exec(withdraw_position_request(1, 1))
+        .expect_failure();
+}
+
+#[test]
+fn test_early_withdraw_penalty_is_paid_to_treasury() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+    let treasury = Key::Account(AccountHash::new([7u8; 32]));
+
+    // 10% until staking_ends, decaying to nothing at withdraw_ends.
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 1_000u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Some(treasury),
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(100i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    let withdraw_request = |block_time: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "withdraw",
+            runtime_args! {
+                "amount" => U256::from(50u64),
+            },
+        )
+        .with_block_time(block_time)
+        .build()
+    };
+
+    // The full 10% of 50 at staking_ends, then half of it halfway to withdraw_ends.
+    builder
+        .exec(withdraw_request(100_000))
+        .expect_success()
+        .commit();
+    builder
+        .exec(withdraw_request(150_000))
+        .expect_success()
+        .commit();
+
+    let treasury_balance = balance_dictionary(&builder, erc20_contract_key, treasury);
+    assert_eq!(treasury_balance, U256::from(5u64 + 2));
+
+    let balance = balance_dictionary(
+        &builder,
+        erc20_contract_key,
+        Key::Account(*DEFAULT_ACCOUNT_ADDR),
+    );
+    assert_eq!(balance, U256::from(500000u64 - 100 + 45 + 48));
+}
+
+#[test]
+fn test_fixed_point_rewards_carry_remainders_and_sweep_dust() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 2u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(5i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(3i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    let add_reward_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "add_reward",
+        runtime_args! {
+            "reward_amount" => U256::from(2i64),
+            "withdrawable_amount" => U256::from(0i64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(add_reward_request).expect_success().commit();
+
+    let withdraw_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "withdraw",
+            runtime_args! {
+                "amount" => U256::from(1u64),
+            },
+        )
+        .with_block_time(200_000)
+        .build()
+    };
+    let sweep_dust_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "sweep_dust",
+            runtime_args! {},
+        )
+        .with_block_time(200_000)
+        .build()
+    };
+    let balance = |builder: &WasmTestBuilder<InMemoryGlobalState>| {
+        balance_dictionary(
+            builder,
+            erc20_contract_key,
+            Key::Account(*DEFAULT_ACCOUNT_ADDR),
+        )
+    };
+
+    // Each unit of stake is owed 2/3 of a reward token, which truncates to nothing on its own.
+    // Carrying the remainders pays one token on the second withdrawal.
+    builder.exec(withdraw_request()).expect_success().commit();
+    assert_eq!(balance(&builder), U256::from(500000u64 - 5 + 1));
+    builder.exec(withdraw_request()).expect_success().commit();
+    assert_eq!(balance(&builder), U256::from(500000u64 - 5 + 3));
+
+    // Dust cannot be swept while a staker is left in the pool.
+    builder.exec(sweep_dust_request()).expect_failure();
+
+    builder.exec(withdraw_request()).expect_success().commit();
+    assert_eq!(balance(&builder), U256::from(500000u64 - 5 + 4));
+
+    builder.exec(sweep_dust_request()).expect_success().commit();
+    assert_eq!(balance(&builder), U256::from(500000u64));
+
+    // Nothing is left to sweep.
+    builder.exec(sweep_dust_request()).expect_success().commit();
+    assert_eq!(balance(&builder), U256::from(500000u64));
+}
+
+#[test]
+fn test_vault_compounds_rewards_into_share_price() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 3u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(150i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(100i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    let add_reward_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "add_reward",
+        runtime_args! {
+            "reward_amount" => U256::from(50i64),
+            "withdrawable_amount" => U256::from(0i64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(add_reward_request).expect_success().commit();
+
+    // Anyone may compound; half of the rewards have been streamed by then.
+    let compound_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "compound",
+        runtime_args! {},
+    )
+    .with_block_time(50_000)
+    .build();
+
+    builder.exec(compound_request).expect_success().commit();
+
+    let redeem_request = |shares: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "redeem",
+            runtime_args! {
+                "shares" => U256::from(shares),
+            },
+        )
+        .with_block_time(200_000)
+        .build()
+    };
+    let balance = |builder: &WasmTestBuilder<InMemoryGlobalState>| {
+        balance_dictionary(
+            builder,
+            erc20_contract_key,
+            Key::Account(*DEFAULT_ACCOUNT_ADDR),
+        )
+    };
+
+    // The 100 shares are worth the 150 tokens of the vault once every reward is compounded.
+    builder.exec(redeem_request(40)).expect_success().commit();
+    assert_eq!(balance(&builder), U256::from(500000u64 - 150 + 60));
+
+    // Rewards are only paid through the share price.
+    let claim_rewards_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "claim_rewards",
+        runtime_args! {},
+    )
+    .with_block_time(200_000)
+    .build();
+
+    builder.exec(claim_rewards_request).expect_failure();
+
+    builder.exec(redeem_request(60)).expect_success().commit();
+    assert_eq!(balance(&builder), U256::from(500000u64));
+}
+
+#[test]
+fn test_stake_receipts_move_the_stake() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(100i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    let recipient = Key::Account(AccountHash::new([7u8; 32]));
+    let transfer_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "transfer",
+        runtime_args! {
+            "recipient" => recipient,
+            "amount" => U256::from(40u64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(transfer_request).expect_success().commit();
+
+    // Receipts beyond the sender's balance, or beyond the allowance of the caller, can not move.
+    let transfer_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "transfer",
+        runtime_args! {
+            "recipient" => recipient,
+            "amount" => U256::from(61u64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(transfer_request).expect_failure();
+
+    let transfer_from_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "transfer_from",
+        runtime_args! {
+            "owner" => recipient,
+            "recipient" => Key::Account(*DEFAULT_ACCOUNT_ADDR),
+            "amount" => U256::from(1u64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(transfer_from_request).expect_failure();
+
+    let receipt_key: Key = staking_contract_hash.into();
+    assert_eq!(
+        balance_dictionary(&builder, receipt_key, Key::Account(*DEFAULT_ACCOUNT_ADDR)),
+        U256::from(60u64)
+    );
+    assert_eq!(
+        balance_dictionary(&builder, receipt_key, recipient),
+        U256::from(40u64)
+    );
+
+    let withdraw_request = |amount: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "withdraw",
+            runtime_args! {
+                "amount" => U256::from(amount),
+            },
+        )
+        .with_block_time(200_000)
+        .build()
+    };
+
+    // The stake moved along with the receipts.
+    builder.exec(withdraw_request(100)).expect_failure();
+    builder.exec(withdraw_request(60)).expect_success().commit();
+
+    assert_eq!(
+        balance_dictionary(&builder, receipt_key, Key::Account(*DEFAULT_ACCOUNT_ADDR)),
+        U256::zero()
+    );
+    assert_eq!(
+        balance_dictionary(
+            &builder,
+            erc20_contract_key,
+            Key::Account(*DEFAULT_ACCOUNT_ADDR)
+        ),
+        U256::from(500000u64 - 40)
+    );
+}
+
+#[test]
+fn test_stake_for_credits_the_beneficiary() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let beneficiary_account = AccountHash::new([7u8; 32]);
+    let beneficiary = Key::Account(beneficiary_account);
+
+    let create_beneficiary_account_request = ExecuteRequestBuilder::transfer(
+        *DEFAULT_ACCOUNT_ADDR,
+        runtime_args! {
+            mint::ARG_AMOUNT => U512::from(MINIMUM_ACCOUNT_CREATION_BALANCE),
+            mint::ARG_TARGET => beneficiary_account,
+            mint::ARG_ID => Option::<u64>::None,
+        },
+    )
+    .build();
+
+    builder
+        .exec(create_beneficiary_account_request)
+        .expect_success()
+        .commit();
+
+    let stake_for_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake_for",
+            runtime_args! {
+                "beneficiary" => beneficiary,
+                "amount" => U256::from(100i64),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => 0u8,
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    // Nobody can open positions for a beneficiary that did not approve it as its operator.
+    builder.exec(stake_for_request()).expect_failure();
+
+    let approve_operator_request = ExecuteRequestBuilder::contract_call_by_hash(
+        beneficiary_account,
+        staking_contract_hash,
+        "approve_operator",
+        runtime_args! {
+            "operator" => Key::Account(*DEFAULT_ACCOUNT_ADDR),
+        },
+    )
+    .build();
+
+    builder
+        .exec(approve_operator_request)
+        .expect_success()
+        .commit();
+
+    builder.exec(stake_for_request()).expect_success().commit();
+
+    // The tokens came out of the caller's allowance, while the stake and its receipts went to the
+    // beneficiary.
+    assert_eq!(
+        balance_dictionary(
+            &builder,
+            erc20_contract_key,
+            Key::Account(*DEFAULT_ACCOUNT_ADDR)
+        ),
+        U256::from(500000u64 - 100)
+    );
+    let receipt_key: Key = staking_contract_hash.into();
+    assert_eq!(
+        balance_dictionary(&builder, receipt_key, beneficiary),
+        U256::from(100u64)
+    );
+
+    let withdraw_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "withdraw",
+        runtime_args! {
+            "amount" => U256::from(100u64),
+        },
+    )
+    .with_block_time(200_000)
+    .build();
+
+    builder.exec(withdraw_request).expect_failure();
+}
+
+#[test]
+fn test_withdraw_for_pays_the_payout_destination() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(100i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    let destination = Key::Account(AccountHash::new([7u8; 32]));
+    let set_payout_destination_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "set_payout_destination",
+        runtime_args! {
+            "destination" => Some(destination),
+        },
+    )
+    .build();
+
+    builder
+        .exec(set_payout_destination_request)
+        .expect_success()
+        .commit();
+
+    // The caller was not approved as an operator of `destination`.
+    let unapproved_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "withdraw_for",
+        runtime_args! {
+            "staker" => destination,
+            "amount" => U256::from(100u64),
+        },
+    )
+    .with_block_time(200_000)
+    .build();
+
+    builder.exec(unapproved_request).expect_failure();
+
+    let withdraw_for_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "withdraw_for",
+        runtime_args! {
+            "staker" => Key::Account(*DEFAULT_ACCOUNT_ADDR),
+            "amount" => U256::from(100u64),
+        },
+    )
+    .with_block_time(200_000)
+    .build();
+
+    builder.exec(withdraw_for_request).expect_success().commit();
+
+    // The principal was paid out to the destination set by the staker.
+    assert_eq!(
+        balance_dictionary(
+            &builder,
+            erc20_contract_key,
+            Key::Account(*DEFAULT_ACCOUNT_ADDR)
+        ),
+        U256::from(500000u64 - 100)
+    );
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, destination),
+        U256::from(100u64)
+    );
+}
+
+#[test]
+fn test_stake_with_permit_signed_off_chain() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let owner_secret_key = SecretKey::ed25519_from_bytes([3u8; 32]).unwrap();
+    let owner_public_key = PublicKey::from(&owner_secret_key);
+    let owner_account = owner_public_key.to_account_hash();
+    let owner = Key::Account(owner_account);
+
+    let create_owner_account_request = ExecuteRequestBuilder::transfer(
+        *DEFAULT_ACCOUNT_ADDR,
+        runtime_args! {
+            mint::ARG_AMOUNT => U512::from(MINIMUM_ACCOUNT_CREATION_BALANCE),
+            mint::ARG_TARGET => owner_account,
+            mint::ARG_ID => Option::<u64>::None,
+        },
+    )
+    .build();
+
+    builder
+        .exec(create_owner_account_request)
+        .expect_success()
+        .commit();
+
+    let transfer_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "transfer",
+        runtime_args! {
+            "recipient" => owner,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(transfer_request).expect_success().commit();
+
+    let deadline = 1_000u64;
+    let digest = permit_digest(
+        erc20_contract_package_hash,
+        owner,
+        staking_contract_key,
+        U256::from(100i64),
+        0,
+        deadline,
+    );
+    let signature = crypto::sign(digest, &owner_secret_key, &owner_public_key);
+    let stake_with_permit_args = |terms_lock_tier: u8| {
+        let terms_digest = stake_terms_digest(staking_contract_key, &signature, terms_lock_tier);
+        runtime_args! {
+            "public_key" => owner_public_key.clone(),
+            "amount" => U256::from(100i64),
+            "deadline" => deadline,
+            "signature" => signature,
+            "terms_signature" => crypto::sign(terms_digest, &owner_secret_key, &owner_public_key),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        }
+    };
+
+    // The owner signed the terms for another lock tier than the one submitted.
+    let tampered_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake_with_permit",
+        stake_with_permit_args(1),
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(tampered_request).expect_failure();
+
+    // The terms cover the lock tier, so anyone can relay the permit for the owner.
+    let stake_with_permit_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake_with_permit",
+        stake_with_permit_args(0),
+    )
+    .with_block_time(0)
+    .build();
+
+    builder
+        .exec(stake_with_permit_request)
+        .expect_success()
+        .commit();
+
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, owner),
+        U256::zero()
+    );
+    let receipt_key: Key = staking_contract_hash.into();
+    assert_eq!(
+        balance_dictionary(&builder, receipt_key, owner),
+        U256::from(100u64)
+    );
+
+    // The nonce of the owner moved on, so the same permit can not be submitted twice.
+    let replayed_request = ExecuteRequestBuilder::contract_call_by_hash(
+        owner_account,
+        staking_contract_hash,
+        "stake_with_permit",
+        stake_with_permit_args(0),
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(replayed_request).expect_failure();
+}
+
+#[test]
+fn test_install_rejects_an_inconsistent_schedule() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        // Withdrawals may not open before staking ends.
+        "withdraw_starts" => 50_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_failure();
+}
+
+#[test]
+fn test_stake_limits_and_address_caps() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+    let staker = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(1000i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let set_stake_limits_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "set_stake_limits",
+        runtime_args! {
+            "min_stake" => U256::from(10u64),
+            "max_stake_per_address" => Some(U256::from(100u64)),
+        },
+    )
+    .build();
+
+    builder
+        .exec(set_stake_limits_request)
+        .expect_success()
+        .commit();
+
+    let stake_request = |amount: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake",
+            runtime_args! {
+                "amount" => U256::from(amount),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => 0u8,
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    // Below the minimum, then up to the maximum per address and one token over it.
+    builder.exec(stake_request(9)).expect_failure();
+    builder.exec(stake_request(100)).expect_success().commit();
+    builder.exec(stake_request(10)).expect_failure();
+
+    let set_stake_cap_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "set_stake_cap",
+        runtime_args! {
+            "staker" => staker,
+            "cap" => Some(U256::from(150u64)),
+        },
+    )
+    .build();
+
+    builder
+        .exec(set_stake_cap_request)
+        .expect_success()
+        .commit();
+
+    // The cap of the address replaces the maximum of the pool.
+    builder.exec(stake_request(50)).expect_success().commit();
+    builder.exec(stake_request(10)).expect_failure();
+
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, staker, 1),
+        (1, U256::from(50u64), 0, 0, 0)
+    );
+
+    // Stake limits and caps only matter while stakes are taken.
+    let late_set_stake_cap_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "set_stake_cap",
+        runtime_args! {
+            "staker" => staker,
+            "cap" => Option::<U256>::None,
+        },
+    )
+    .with_block_time(100_000)
+    .build();
+
+    builder.exec(late_set_stake_cap_request).expect_failure();
+}
+
+#[test]
+fn test_allowlisted_pool_requires_a_proof_within_the_allocation() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+
+    let staker = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+    let allowlist = [
+        (staker, U256::from(100u64)),
+        (
+            Key::Account(AccountHash::new([7u8; 32])),
+            U256::from(500u64),
+        ),
+        (
+            Key::Account(AccountHash::new([8u8; 32])),
+            U256::from(500u64),
+        ),
+    ];
+    let tree = MerkleTree::new(&allowlist, |bytes| create_blake2b_hash(bytes)).unwrap();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Some(tree.root()),
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(1000i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_with_proof_request = |amount: u64, allocation: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake_with_proof",
+            runtime_args! {
+                "amount" => U256::from(amount),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => 0u8,
+                "allocation" => U256::from(allocation),
+                "proof" => tree.proof(0).unwrap(),
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+    let stake_request = |amount: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake",
+            runtime_args! {
+                "amount" => U256::from(amount),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => 0u8,
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    // No proof, then a proof of an allocation the allowlist does not grant.
+    builder.exec(stake_request(60)).expect_failure();
+    builder
+        .exec(stake_with_proof_request(60, 500))
+        .expect_failure();
+    builder
+        .exec(stake_with_proof_request(60, 100))
+        .expect_success()
+        .commit();
+    // Over the allocation once the first stake is counted.
+    builder
+        .exec(stake_with_proof_request(50, 100))
+        .expect_failure();
+
+    // Receipts can not move the stake to an address outside of the allowlist.
+    let transfer_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "transfer",
+            runtime_args! {
+                "recipient" => Key::Account(AccountHash::new([7u8; 32])),
+                "amount" => U256::from(10u64),
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    builder.exec(transfer_request()).expect_failure();
+
+    let open_pool_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "set_allowlist_root",
+        runtime_args! {
+            "root" => Option::<[u8; 32]>::None,
+        },
+    )
+    .build();
+
+    builder.exec(open_pool_request).expect_success().commit();
+    builder.exec(stake_request(50)).expect_success().commit();
+
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, staker, 1),
+        (1, U256::from(50u64), 0, 0, 0)
+    );
+
+    builder.exec(transfer_request()).expect_success().commit();
+}
+
+#[test]
+fn test_referrer_is_credited_a_share_of_claimed_rewards() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 1u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let set_referral_rate_request = |referral_rate: u64| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "set_referral_rate",
+            runtime_args! {
+                "referral_rate" => referral_rate,
+            },
+        )
+        .build()
+    };
+
+    // Over 100%, then 20% of the rewards.
+    builder
+        .exec(set_referral_rate_request(10_001))
+        .expect_failure();
+    builder
+        .exec(set_referral_rate_request(2_000))
+        .expect_success()
+        .commit();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = |referrer: Key| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake",
+            runtime_args! {
+                "amount" => U256::from(5i64),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => 0u8,
+                "referrer" => Some(referrer),
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    let referrer = Key::Account(AccountHash::new([7u8; 32]));
+    builder
+        .exec(stake_request(Key::Account(*DEFAULT_ACCOUNT_ADDR)))
+        .expect_failure();
+    builder
+        .exec(stake_request(referrer))
+        .expect_success()
+        .commit();
+    // The referrer can only be named on the first stake.
+    builder.exec(stake_request(referrer)).expect_failure();
+
+    // 20 tokens streamed over the 100 seconds of the staking window.
+    let add_reward_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "add_reward",
+        runtime_args! {
+            "reward_amount" => U256::from(20i64),
+            "withdrawable_amount" => U256::from(0i64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(add_reward_request).expect_success().commit();
+
+    let claim_rewards_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "claim_rewards",
+        runtime_args! {},
+    )
+    .with_block_time(50_000)
+    .build();
+
+    builder
+        .exec(claim_rewards_request)
+        .expect_success()
+        .commit();
+
+    // Of the 10 tokens streamed so far, the referrer is paid 2 and the staker the other 8.
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, referrer),
+        U256::from(2u64)
+    );
+    assert_eq!(
+        balance_dictionary(
+            &builder,
+            erc20_contract_key,
+            Key::Account(*DEFAULT_ACCOUNT_ADDR)
+        ),
+        U256::from(500000u64 - 5 - 20 + 8)
+    );
+}
+
+#[test]
+fn test_early_claim_keeps_the_pro_rata_rewards_whole() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(10i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    // 20 tokens of rewards, 10 of which are paid out over the withdraw window.
+    let add_reward_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "add_reward",
+        runtime_args! {
+            "reward_amount" => U256::from(20i64),
+            "withdrawable_amount" => U256::from(10i64),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(add_reward_request).expect_success().commit();
+
+    // Halfway through the withdraw window, half of the early withdraw reward.
+    let claim_rewards_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "claim_rewards",
+        runtime_args! {},
+    )
+    .with_block_time(150_000)
+    .build();
+
+    builder
+        .exec(claim_rewards_request)
+        .expect_success()
+        .commit();
+
+    let staker = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, staker),
+        U256::from(500000u64 - 10 - 20 + 5)
+    );
+
+    let withdraw_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "withdraw",
+        runtime_args! {
+            "amount" => U256::from(5u64),
+        },
+    )
+    .with_block_time(200_000)
+    .build();
+
+    builder.exec(withdraw_request).expect_success().commit();
+
+    // Half of the 20 tokens, less the half of the claimed 5 settled on this half of the stake.
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, staker),
+        U256::from(500000u64 - 10 - 20 + 5 + 5 + 8)
+    );
+
+    let withdraw_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "withdraw",
+        runtime_args! {
+            "amount" => U256::from(5u64),
+        },
+    )
+    .with_block_time(200_000)
+    .build();
+
+    builder.exec(withdraw_request).expect_success().commit();
+
+    // The whole stake and all 20 tokens of rewards are back with the staker.
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, staker),
+        U256::from(500000u64)
+    );
+}
+
+#[test]
+fn test_stake_over_the_cap_records_a_refund() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    // The pool takes at most 10 tokens.
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(10i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(15i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    // Only the 10 tokens of room were taken.
+    assert_eq!(
+        balance_dictionary(
+            &builder,
+            erc20_contract_key,
+            Key::Account(*DEFAULT_ACCOUNT_ADDR)
+        ),
+        U256::from(500000u64 - 10)
+    );
+
+    let named_keys = builder
+        .query(None, Key::from(staking_contract_hash), &[])
+        .unwrap()
+        .as_contract()
+        .expect("must have staking contract")
+        .named_keys()
+        .clone();
+    let events_length: u32 = builder
+        .query(None, named_keys["__events_length"], &[])
+        .unwrap()
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone()
+        .into_t()
+        .expect("must convert to u32");
+    let events_uref = *named_keys["__events"].as_uref().expect("must be a uref");
+    let event = builder
+        .query_dictionary_item(None, events_uref, &(events_length - 1).to_string())
+        .expect("should be stored value.")
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone();
+
+    let (event_name, bytes) = String::from_bytes(event.inner_bytes()).unwrap();
+    assert_eq!(event_name, "event_Refunded");
+    let (_token_address, bytes) = String::from_bytes(bytes).unwrap();
+    let (staker_address, bytes) = Key::from_bytes(bytes).unwrap();
+    assert_eq!(staker_address, Key::Account(*DEFAULT_ACCOUNT_ADDR));
+    let (amount, bytes) = U256::from_bytes(bytes).unwrap();
+    assert_eq!(amount, U256::from(5u64));
+    assert!(bytes.is_empty());
+
+    // The pool is full.
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(1i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_failure();
+}
+
+#[test]
+fn test_allowlisted_pool_only_takes_stakes_with_a_proof() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+
+    let owner_secret_key = SecretKey::ed25519_from_bytes([3u8; 32]).unwrap();
+    let owner_public_key = PublicKey::from(&owner_secret_key);
+    let owner_account = owner_public_key.to_account_hash();
+    let owner = Key::Account(owner_account);
+
+    // Both stakers are allowlisted, so only the missing proof makes their stakes fail.
+    let staker = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+    let allowlist = [(staker, U256::from(100u64)), (owner, U256::from(100u64))];
+    let tree = MerkleTree::new(&allowlist, |bytes| create_blake2b_hash(bytes)).unwrap();
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Some(tree.root()),
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let create_owner_account_request = ExecuteRequestBuilder::transfer(
+        *DEFAULT_ACCOUNT_ADDR,
+        runtime_args! {
+            mint::ARG_AMOUNT => U512::from(MINIMUM_ACCOUNT_CREATION_BALANCE),
+            mint::ARG_TARGET => owner_account,
+            mint::ARG_ID => Option::<u64>::None,
+        },
+    )
+    .build();
+
+    builder
+        .exec(create_owner_account_request)
+        .expect_success()
+        .commit();
+
+    let transfer_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "transfer",
+        runtime_args! {
+            "recipient" => owner,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(transfer_request).expect_success().commit();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(10i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_failure();
+
+    let stake_for_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake_for",
+        runtime_args! {
+            "beneficiary" => owner,
+            "amount" => U256::from(10i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_for_request).expect_failure();
+
+    let deadline = 1_000u64;
+    let digest = permit_digest(
+        erc20_contract_package_hash,
+        owner,
+        staking_contract_key,
+        U256::from(10i64),
+        0,
+        deadline,
+    );
+    let signature = crypto::sign(digest, &owner_secret_key, &owner_public_key);
+    let terms_digest = stake_terms_digest(staking_contract_key, &signature, 0);
+    let terms_signature = crypto::sign(terms_digest, &owner_secret_key, &owner_public_key);
+    let stake_with_permit_request = ExecuteRequestBuilder::contract_call_by_hash(
+        owner_account,
+        staking_contract_hash,
+        "stake_with_permit",
+        runtime_args! {
+            "public_key" => owner_public_key,
+            "amount" => U256::from(10i64),
+            "deadline" => deadline,
+            "signature" => signature,
+            "terms_signature" => terms_signature,
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_with_permit_request).expect_failure();
+
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, owner),
+        U256::from(100u64)
+    );
+
+    let stake_with_proof_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake_with_proof",
+        runtime_args! {
+            "amount" => U256::from(10i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+            "allocation" => U256::from(100i64),
+            "proof" => tree.proof(0).unwrap(),
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder
+        .exec(stake_with_proof_request)
+        .expect_success()
+        .commit();
+
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, staker, 0),
+        (0, U256::from(10u64), 0, 0, 0)
+    );
+}
+
+#[test]
+fn test_vault_pool_rejects_referrers() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 3u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Option::<Key>::None,
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = |referrer: Option<Key>| {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "stake",
+            runtime_args! {
+                "amount" => U256::from(5i64),
+                "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+                "lock_tier" => 0u8,
+                "referrer" => referrer,
+            },
+        )
+        .with_block_time(0)
+        .build()
+    };
+
+    // Vault rewards are compounded rather than paid, so no referrer could ever be credited.
+    let referrer = Key::Account(AccountHash::new([7u8; 32]));
+    builder.exec(stake_request(Some(referrer))).expect_failure();
+    builder.exec(stake_request(None)).expect_success().commit();
+}
+
+#[test]
+fn test_position_nft_decides_who_withdraws_and_claims() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+    let (nft_contract_hash, nft_contract_package_hash) = install_position_nft(&mut builder);
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Some(Key::from(nft_contract_package_hash)),
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+
+    let buyer_account = AccountHash::new([7u8; 32]);
+    let buyer = Key::Account(buyer_account);
+    let staker = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+
+    let create_buyer_account_request = ExecuteRequestBuilder::transfer(
+        *DEFAULT_ACCOUNT_ADDR,
+        runtime_args! {
+            mint::ARG_AMOUNT => U512::from(MINIMUM_ACCOUNT_CREATION_BALANCE),
+            mint::ARG_TARGET => buyer_account,
+            mint::ARG_ID => Option::<u64>::None,
+        },
+    )
+    .build();
+
+    builder
+        .exec(create_buyer_account_request)
+        .expect_success()
+        .commit();
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(10i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    // The staker sells the NFT of its position.
+    let transfer_nft_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        nft_contract_hash,
+        "transfer",
+        runtime_args! {
+            "token_id" => 0u64,
+            "source_key" => staker,
+            "target_key" => buyer,
+        },
+    )
+    .build();
+
+    builder.exec(transfer_nft_request).expect_success().commit();
+
+    let claim_rewards_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "claim_rewards",
+            runtime_args! {},
+        )
+        .with_block_time(150_000)
+        .build()
+    };
+
+    // The staker no longer holds the NFT of its position, so it can neither claim nor withdraw it.
+    builder.exec(claim_rewards_request()).expect_failure();
+
+    let withdraw_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "withdraw",
+        runtime_args! {
+            "amount" => U256::from(10i64),
+        },
+    )
+    .with_block_time(150_000)
+    .build();
+
+    builder.exec(withdraw_request).expect_failure();
+
+    let staker_withdraw_nft_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "withdraw_nft",
+            runtime_args! {
+                "token_id" => 0u64,
+            },
+        )
+        .with_block_time(150_000)
+        .build()
+    };
+
+    builder.exec(staker_withdraw_nft_request()).expect_failure();
+
+    // Claiming through the NFT moves the position to the buyer, which keeps the NFT.
+    let claim_rewards_nft_request = ExecuteRequestBuilder::contract_call_by_hash(
+        buyer_account,
+        staking_contract_hash,
+        "claim_rewards_nft",
+        runtime_args! {
+            "token_id" => 0u64,
+        },
+    )
+    .with_block_time(150_000)
+    .build();
+
+    builder
+        .exec(claim_rewards_nft_request)
+        .expect_success()
+        .commit();
+
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, staker, 0).1,
+        U256::zero()
+    );
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, buyer, 0).1,
+        U256::from(10u64)
+    );
+    assert_eq!(
+        live_positions_dictionary(&builder, staking_contract_hash, buyer),
+        vec![0]
+    );
+
+    // The buyer lets the pool burn the NFT and withdraws the position.
+    let approve_nft_request = ExecuteRequestBuilder::contract_call_by_hash(
+        buyer_account,
+        nft_contract_hash,
+        "approve",
+        runtime_args! {
+            "token_id" => 0u64,
+            "operator" => Key::from(staking_contract_hash),
+        },
+    )
+    .build();
+
+    builder.exec(approve_nft_request).expect_success().commit();
+
+    let buyer_withdraw_nft_request = ExecuteRequestBuilder::contract_call_by_hash(
+        buyer_account,
+        staking_contract_hash,
+        "withdraw_nft",
+        runtime_args! {
+            "token_id" => 0u64,
+        },
+    )
+    .with_block_time(150_000)
+    .build();
+
+    builder
+        .exec(buyer_withdraw_nft_request)
+        .expect_success()
+        .commit();
+
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, buyer),
+        U256::from(10u64)
+    );
+    assert!(live_positions_dictionary(&builder, staking_contract_hash, buyer).is_empty());
+
+    // The NFT is burnt, and the staker that opened the position is still rejected.
+    builder.exec(staker_withdraw_nft_request()).expect_failure();
+}
+
+#[test]
+fn test_emergency_withdraw_nft_pays_back_the_holder() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+    let erc20_contract_key: Key = erc20_contract_hash.into();
+    let (nft_contract_hash, nft_contract_package_hash) = install_position_nft(&mut builder);
+
+    let staking_contract_runtime_args = runtime_args! {
+        "name" => "FerrumX".to_string(),
+        "address" => ADDRESS.to_string(),
+        "staking_starts" => 0u64,
+        "staking_ends" => 100_000u64,
+        "withdraw_starts" => 100_000u64,
+        "withdraw_ends" => 200_000u64,
+        "staking_total" => U256::from(500000i64),
+        "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+        "reward_mode" => 0u8,
+        "lock_tiers" => Vec::<(u64, u64)>::new(),
+        "early_withdraw_penalty" => 0u64,
+        "penalty_mode" => 0u8,
+        "treasury" => Option::<Key>::None,
+        "position_nft" => Some(Key::from(nft_contract_package_hash)),
+        "allowlist_root" => Option::<[u8; 32]>::None,
+    };
+
+    let staking_contract_install_request = ExecuteRequestBuilder::standard(
+        *DEFAULT_ACCOUNT_ADDR,
+        STAKING_WASM,
+        staking_contract_runtime_args,
+    )
+    .build();
+
+    builder
+        .exec(staking_contract_install_request)
+        .expect_success()
+        .commit();
+
+    let staking_contract_package_hash = get_stacking_contract_package_hash(&builder);
+    let staking_contract_hash = get_stacking_contract_hash(&builder);
+    let staking_contract_key: Key = staking_contract_package_hash.into();
+    let staker = Key::Account(*DEFAULT_ACCOUNT_ADDR);
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        runtime_args! {
+            "spender" => staking_contract_key,
+            "amount" => U256::from(100i64),
+        },
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "stake",
+        runtime_args! {
+            "amount" => U256::from(10i64),
+            "staking_contract_package_hash" => staking_contract_package_hash.to_formatted_string(),
+            "lock_tier" => 0u8,
+        },
+    )
+    .with_block_time(0)
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    let approve_nft_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        nft_contract_hash,
+        "approve",
+        runtime_args! {
+            "token_id" => 0u64,
+            "operator" => Key::from(staking_contract_hash),
+        },
+    )
+    .build();
+
+    builder.exec(approve_nft_request).expect_success().commit();
+
+    let emergency_withdraw_nft_request = || {
+        ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            staking_contract_hash,
+            "emergency_withdraw_nft",
+            runtime_args! {
+                "token_id" => 0u64,
+            },
+        )
+        .with_block_time(50_000)
+        .build()
+    };
+
+    // Emergency withdrawals are only available while paused.
+    builder
+        .exec(emergency_withdraw_nft_request())
+        .expect_failure();
+
+    let pause_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "pause",
+        runtime_args! {},
+    )
+    .build();
+
+    builder.exec(pause_request).expect_success().commit();
+
+    // Pools minting position NFTs only pay back positions through their NFT.
+    let emergency_withdraw_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        staking_contract_hash,
+        "emergency_withdraw",
+        runtime_args! {},
+    )
+    .with_block_time(50_000)
+    .build();
+
+    builder.exec(emergency_withdraw_request).expect_failure();
+
+    builder
+        .exec(emergency_withdraw_nft_request())
+        .expect_success()
+        .commit();
+
+    // The position is paid back before its lock ends, and its NFT is burnt.
+    assert_eq!(
+        balance_dictionary(&builder, erc20_contract_key, staker),
+        U256::from(500000u64)
+    );
+    assert_eq!(
+        position_dictionary(&builder, staking_contract_hash, staker, 0).1,
+        U256::zero()
+    );
+    assert!(live_positions_dictionary(&builder, staking_contract_hash, staker).is_empty());
+
+    builder
+        .exec(emergency_withdraw_nft_request())
+        .expect_failure();
+}
+
+#[test]
+fn test_factory_create_pool_and_stake() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+    let erc20_runtime_args = runtime_args! {
+        "name" => "FERRUM_ERC20".to_string(),
+        "symbol" => "F_ERC20".to_string(),
+        "total_supply" => U256::from(500000i64),
+        "decimals" => 8u8,
+    };
+
+    let erc_20_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, ERC20_WASM, erc20_runtime_args)
+            .build();
+
+    builder
+        .exec(erc_20_install_request)
+        .expect_success()
+        .commit();
+
+    let erc20_contract_hash = get_erc20_contract_hash(&builder);
+    let erc20_contract_package_hash = get_erc20_contract_package_hash(&builder);
+
+    let factory_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, FACTORY_WASM, runtime_args! {})
+            .build();
+
+    builder
+        .exec(factory_install_request)
+        .expect_success()
+        .commit();
+
+    let factory_contract_hash = get_named_contract_hash(&builder, FACTORY_CONTRACT_HASH);
+    let factory_contract_package_hash =
+        get_named_contract_package_hash(&builder, FACTORY_CONTRACT_PACKAGE_HASH);
+
+    for name in ["FerrumX", "FerrumY"] {
+        let create_pool_args = runtime_args! {
+            "name" => name.to_string(),
+            "address" => ADDRESS.to_string(),
+            "staking_starts" => 0u64,
+            "staking_ends" => 1781708875776u64,
+            "withdraw_starts" => 1781708875776u64,
+            "withdraw_ends" => 1781708975776u64,
+            "staking_total" => U256::from(500000i64),
+            "erc20_contract_package_hash" => Key::from(erc20_contract_package_hash),
+            "reward_token_package_hash" => Key::from(erc20_contract_package_hash),
+            "reward_mode" => 0u8,
+            "lock_tiers" => Vec::<(u64, u64)>::new(),
+            "early_withdraw_penalty" => 0u64,
+            "penalty_mode" => 0u8,
+            "treasury" => Option::<Key>::None,
+            "position_nft" => Option::<Key>::None,
+            "allowlist_root" => Option::<[u8; 32]>::None,
+        };
+
+        let create_pool_request = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            factory_contract_hash,
+            "create_pool",
+            create_pool_args,
+        )
+        .build();
+
+        builder.exec(create_pool_request).expect_success().commit();
+    }
+
+    let pool_count: u64 = builder
+        .query(
+            None,
+            Key::from(factory_contract_hash),
+            &["pool_count".to_string()],
+        )
+        .expect("should have pool_count")
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone()
+        .into_t()
+        .expect("must convert to u64");
+
+    assert_eq!(pool_count, 2);
+
+    let factory_contract_key: Key = factory_contract_package_hash.into();
+
+    let approve_args = runtime_args! {
+        "spender" => factory_contract_key,
+        "amount" => U256::from(10i64),
+    };
+
+    let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        erc20_contract_hash,
+        "approve",
+        approve_args,
+    )
+    .build();
+
+    builder.exec(approve_request).expect_success().commit();
+
+    let stake_args = runtime_args! {
+        "pool_id" => 1u64,
+        "amount" => U256::from(5i64),
+        "staking_contract_package_hash" => factory_contract_package_hash.to_formatted_string(),
+        "lock_tier" => 0u8,
+    };
+
+    let stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        factory_contract_hash,
+        "stake",
+        stake_args,
+    )
+    .build();
+
+    builder.exec(stake_request).expect_success().commit();
+
+    // The stake is recorded in the event log of pool 1 only.
+    let named_keys = builder
+        .query(None, Key::from(factory_contract_hash), &[])
+        .unwrap()
+        .as_contract()
+        .expect("must have factory contract")
+        .named_keys()
+        .clone();
+
+    for (pool_id, expected_length) in [(0u64, 0u32), (1, 1)] {
+        let pool_uref = *named_keys[&format!("pool_{pool_id}")]
+            .as_uref()
+            .expect("must be a uref");
+        let events_length: Option<u32> = builder
+            .query_dictionary_item(None, pool_uref, "__events_length")
+            .expect("should be stored value.")
+            .as_cl_value()
+            .expect("should be cl value.")
+            .clone()
+            .into_t()
+            .expect("must convert to u32");
+        assert_eq!(events_length, Some(expected_length));
+    }
+
+    let events_uref = *named_keys["__events_1"].as_uref().expect("must be a uref");
+    let event = builder
+        .query_dictionary_item(None, events_uref, "0")
+        .expect("should be stored value.")
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone();
+    let (event_name, _) = String::from_bytes(event.inner_bytes()).unwrap();
+    assert_eq!(event_name, "event_Stake");
+
+    // The factory log keeps only the role grants made at install.
+    let events_length: u32 = builder
+        .query(None, named_keys["__events_length"], &[])
+        .unwrap()
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone()
+        .into_t()
+        .expect("must convert to u32");
+    assert_eq!(events_length, 3);
+
+    let unknown_pool_stake_args = runtime_args! {
+        "pool_id" => 2u64,
+        "amount" => U256::from(5i64),
+        "staking_contract_package_hash" => factory_contract_package_hash.to_formatted_string(),
+        "lock_tier" => 0u8,
+    };
+
+    let unknown_pool_stake_request = ExecuteRequestBuilder::contract_call_by_hash(
+        *DEFAULT_ACCOUNT_ADDR,
+        factory_contract_hash,
+        "stake",
+        unknown_pool_stake_args,
+    )
+    .build();
+
+    builder.exec(unknown_pool_stake_request).expect_failure();
+}
+
+/// Reads the position `position_id` of `staker` as `(id, amount, timestamp, lock_tier, unlock_time)`.
+fn position_dictionary(
+    builder: &WasmTestBuilder<InMemoryGlobalState>,
+    staking_contract_hash: ContractHash,
+    staker: Key,
+    position_id: u64,
+) -> (u64, U256, u64, u8, u64) {
+    let positions_uref = *builder
+        .query(None, Key::from(staking_contract_hash), &[])
+        .unwrap()
+        .as_contract()
+        .expect("must have staking contract")
+        .named_keys()["positions"]
+        .as_uref()
+        .expect("must be a uref");
+
+    let mut preimage = staker.to_bytes().unwrap();
+    preimage.append(&mut position_id.to_bytes().unwrap());
+    let dict_item_key = hex::encode(create_blake2b_hash(&preimage));
+
+    let position = builder
+        .query_dictionary_item(None, positions_uref, &dict_item_key)
+        .expect("should be stored value.")
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone();
+
+    let (id, bytes) = u64::from_bytes(position.inner_bytes()).unwrap();
+    let (amount, bytes) = U256::from_bytes(bytes).unwrap();
+    let (timestamp, bytes) = u64::from_bytes(bytes).unwrap();
+    let (lock_tier, bytes) = u8::from_bytes(bytes).unwrap();
+    let (unlock_time, bytes) = u64::from_bytes(bytes).unwrap();
+    assert!(bytes.is_empty());
+    (id, amount, timestamp, lock_tier, unlock_time)
+}
+
+/// Reads the ids of the positions of `staker` still holding tokens.
+fn live_positions_dictionary(
+    builder: &WasmTestBuilder<InMemoryGlobalState>,
+    staking_contract_hash: ContractHash,
+    staker: Key,
+) -> Vec<u64> {
+    let live_positions_uref = *builder
+        .query(None, Key::from(staking_contract_hash), &[])
+        .unwrap()
+        .as_contract()
+        .expect("must have staking contract")
+        .named_keys()["live_positions"]
+        .as_uref()
+        .expect("must be a uref");
+
+    let dict_item_key = match staker {
+        Key::Account(account) => account.to_string(),
+        Key::Hash(package) => hex::encode(package),
+        _ => panic!("stakers are accounts or packages"),
+    };
+
+    let ids: Option<Vec<u64>> = builder
+        .query_dictionary_item(None, live_positions_uref, &dict_item_key)
+        .expect("should be stored value.")
+        .as_cl_value()
+        .expect("should be cl value.")
+        .clone()
+        .into_t()
+        .expect("must convert to Vec<u64>");
+    ids.unwrap_or_default()
+}
+
+/// Creates a dictionary item key for an (owner, spender) pair.
+fn make_allowances_dictionary_item_key(owner: Key, spender: Key) -> String {
+    let mut preimage = Vec::new();
+    preimage.append(&mut owner.to_bytes().unwrap());
+    preimage.append(&mut spender.to_bytes().unwrap());
+
+    let key_bytes = create_blake2b_hash(&preimage);
+    hex::encode(&key_bytes)
+}
+
+/// Digest of the permit `owner` signs to let `spender` transfer `amount` of `token`, as computed by
+/// `casper_erc20::permit::permit_digest`.
+fn permit_digest(
+    token: ContractPackageHash,
+    owner: Key,
+    spender: Key,
+    amount: U256,
+    nonce: u64,
+    deadline: u64,
+) -> [u8; BLAKE2B_DIGEST_LENGTH] {
+    let mut preimage = Vec::new();
+    preimage.append(&mut "casper_erc20:permit".to_bytes().unwrap());
+    preimage.append(&mut token.to_bytes().unwrap());
+    preimage.append(&mut owner.to_bytes().unwrap());
+    preimage.append(&mut spender.to_bytes().unwrap());
+    preimage.append(&mut amount.to_bytes().unwrap());
+    preimage.append(&mut nonce.to_bytes().unwrap());
+    preimage.append(&mut deadline.to_bytes().unwrap());
+    create_blake2b_hash(preimage)
+}
+
+/// Digest of the stake terms the owner of a permit signed with `permit_signature` signs to stake in
+/// the tier `lock_tier` of `pool`.
+fn stake_terms_digest(
+    pool: Key,
+    permit_signature: &Signature,
+    lock_tier: u8,
+) -> [u8; BLAKE2B_DIGEST_LENGTH] {
+    create_blake2b_hash(stake_terms_preimage(&pool, permit_signature, lock_tier).unwrap())
+}
+
+pub(crate) fn create_blake2b_hash<T: AsRef<[u8]>>(data: T) -> [u8; BLAKE2B_DIGEST_LENGTH] {
+    let mut result = [0; BLAKE2B_DIGEST_LENGTH];
+    // NOTE: Assumed safe as `BLAKE2B_DIGEST_LENGTH` is a valid value for a hasher
+    let mut hasher = VarBlake2b::new(BLAKE2B_DIGEST_LENGTH).expect("should create hasher");
+
+    hasher.update(data);
+    hasher.finalize_variable(|slice| {
+        result.copy_from_slice(slice);
+    });
+    result
+}
+
+pub fn get_stacking_contract_package_hash(
+    builder: &WasmTestBuilder<InMemoryGlobalState>,
+) -> ContractPackageHash {
+    let erc20_hash_addr = builder
+        .get_expected_account(*DEFAULT_ACCOUNT_ADDR)
+        .named_keys()
+        .get(STAKING_CONTRACT_PACKAGE_HASH)
+        .expect("must have this entry in named keys")
+        .into_hash()
+        .expect("must get hash_addr");
+
+    ContractPackageHash::new(erc20_hash_addr)
+}
+
+pub fn get_stacking_contract_hash(builder: &WasmTestBuilder<InMemoryGlobalState>) -> ContractHash {
+    let erc20_hash_addr = builder
+        .get_expected_account(*DEFAULT_ACCOUNT_ADDR)
+        .named_keys()
+        .get(STAKING_CONTRACT_HASH)
+        .expect("must have this entry in named keys")
+        .into_hash()
+        .expect("must get hash_addr");
+
+    ContractHash::new(erc20_hash_addr)
+}
+
+/// Installs a CEP-78 collection minting position NFTs with ordinal identifiers and raw metadata,
+/// which anyone can mint and holders can burn. Returns its contract and package hashes.
+fn install_position_nft(
+    builder: &mut WasmTestBuilder<InMemoryGlobalState>,
+) -> (ContractHash, ContractPackageHash) {
+    let cep78_runtime_args = runtime_args! {
+        "collection_name" => POSITION_NFT_COLLECTION_NAME.to_string(),
+        "collection_symbol" => "F_POS".to_string(),
+        "total_token_supply" => 100u64,
+        "ownership_mode" => 2u8,
+        "nft_kind" => 1u8,
+        "holder_mode" => 2u8,
+        "whitelist_mode" => 0u8,
+        "minting_mode" => 1u8,
+        "identifier_mode" => 0u8,
+        "metadata_mutability" => 0u8,
+        "nft_metadata_kind" => 3u8,
+        "json_schema" => "".to_string(),
+        "burn_mode" => 0u8,
+        "allow_minting" => true,
+    };
+
+    let cep78_install_request =
+        ExecuteRequestBuilder::standard(*DEFAULT_ACCOUNT_ADDR, CEP78_WASM, cep78_runtime_args)
+            .build();
+
+    builder
+        .exec(cep78_install_request)
+        .expect_success()
+        .commit();
+
+    (
+        get_named_contract_hash(
+            builder,
+            &format!("cep78_contract_hash_{}", POSITION_NFT_COLLECTION_NAME),
+        ),
+        get_named_contract_package_hash(
+            builder,
+            &format!("cep78_contract_package_{}", POSITION_NFT_COLLECTION_NAME),
+        ),
+    )
+}
+
+fn get_named_contract_hash(
+    builder: &WasmTestBuilder<InMemoryGlobalState>,
+    name: &str,
+) -> ContractHash {
+    let hash_addr = builder
+        .get_expected_account(*DEFAULT_ACCOUNT_ADDR)
+        .named_keys()
+        .get(name)
+        .expect("must have this entry in named keys")
+        .into_hash()
+        .expect("must get hash_addr");
+
+    ContractHash::new(hash_addr)
+}
+
+fn get_named_contract_package_hash(
+    builder: &WasmTestBuilder<InMemoryGlobalState>,
+    name: &str,
+) -> ContractPackageHash {
+    let hash_addr = builder
+        .get_expected_account(*DEFAULT_ACCOUNT_ADDR)
+        .named_keys()
+        .get(name)
+        .expect("must have this entry in named keys")
+        .into_hash()
+        .expect("must get hash_addr");
+
+    ContractPackageHash::new(hash_addr)
 }
 
 pub(crate) fn get_erc20_contract_hash(
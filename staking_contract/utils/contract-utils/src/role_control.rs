@@ -0,0 +1,61 @@
+use casper_contract::contract_api::runtime;
+use casper_types::{ApiError, Key};
+
+use crate::{key_and_value_to_str, ContractContext, ContractStorage, Dict};
+
+const ROLES_DICT: &str = "roles";
+
+pub trait RoleControl<Storage: ContractStorage>: ContractContext<Storage> {
+    fn init(&mut self) {
+        Roles::init();
+    }
+
+    fn grant_role_without_checked(&mut self, role: u8, address: Key) {
+        Roles::instance().grant_role(role, &address);
+    }
+
+    fn revoke_role_without_checked(&mut self, role: u8, address: Key) {
+        Roles::instance().revoke_role(role, &address);
+    }
+
+    fn assert_caller_has_role(&self, role: u8) {
+        let caller = self.get_caller();
+        if !self.has_role(role, caller) {
+            runtime::revert(ApiError::PermissionDenied);
+        }
+    }
+
+    fn has_role(&self, role: u8, address: Key) -> bool {
+        Roles::instance().has_role(role, &address)
+    }
+}
+
+struct Roles {
+    dict: Dict,
+}
+
+impl Roles {
+    pub fn instance() -> Roles {
+        Roles {
+            dict: Dict::instance(ROLES_DICT),
+        }
+    }
+
+    pub fn init() {
+        Dict::init(ROLES_DICT);
+    }
+
+    pub fn has_role(&self, role: u8, key: &Key) -> bool {
+        self.dict
+            .get::<()>(&key_and_value_to_str(key, &role))
+            .is_some()
+    }
+
+    pub fn grant_role(&self, role: u8, key: &Key) {
+        self.dict.set(&key_and_value_to_str(key, &role), ());
+    }
+
+    pub fn revoke_role(&self, role: u8, key: &Key) {
+        self.dict.remove::<()>(&key_and_value_to_str(key, &role));
+    }
+}